@@ -4,7 +4,11 @@ use std::path::PathBuf;
 use color_eyre::eyre::Result;
 use tracing::{info, instrument};
 
-use crate::{cli::ApplyArgs, config::Config, matching::Matcher};
+use crate::{
+    cli::ApplyArgs,
+    config::Config,
+    matching::{self, Context, Request},
+};
 
 #[instrument(level = "debug", skip(args, config_path))]
 pub fn run(args: ApplyArgs, config_path: PathBuf) -> Result<()> {
@@ -20,12 +24,12 @@ pub fn run(args: ApplyArgs, config_path: PathBuf) -> Result<()> {
     };
     input = input.trim_end_matches(&['\n', '\r'][..]).to_string();
 
-    let cfg_str = crate::config::read(&config_path)?;
-    let cfg: Config = Config::parse(&cfg_str)?;
+    let cfg: Config = crate::config::load(&config_path)?;
 
-    match cfg.matcher.apply(&input)? {
-        Some(url) => {
-            print!("{url}");
+    let mut ctx = Context::new(Request::from_input(&input));
+    match matching::evaluate_request(&cfg.matcher, &mut ctx)? {
+        Some(resolution) => {
+            print!("{}", resolution.value());
             io::stdout().flush()?;
             Ok(())
         }
@@ -1,4 +1,4 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -35,6 +35,16 @@ pub enum Commands {
     Daemon(DaemonArgs),
     /// Apply the config to a single URL
     Apply(ApplyArgs),
+    /// Check a config file for errors
+    Validate(ValidateArgs),
+    /// Show how the config evaluates a single URL
+    Explain(ExplainArgs),
+    /// Run the tests and examples embedded in a config file
+    Test(TestArgs),
+    /// Print the JSON Schema of the config file
+    Schema(SchemaArgs),
+    /// Create a config file from a template
+    Init(InitArgs),
     /// Install systemd user service and socket
     Install(InstallArgs),
     /// Uninstall systemd user service and socket
@@ -57,6 +67,53 @@ pub struct ApplyArgs {
     pub url: Option<String>,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct ValidateArgs {
+    /// Config file to check (defaults to the configured one)
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExplainArgs {
+    /// URL to explain
+    pub input: String,
+    /// Print the trace as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TestArgs {
+    /// Config file to test (defaults to the configured one)
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SchemaArgs {}
+
+#[derive(Args, Debug, Clone)]
+pub struct InitArgs {
+    /// Config file to create (defaults to the configured one)
+    pub file: Option<PathBuf>,
+    /// Shortcuts to start with
+    #[arg(long, value_enum, default_value_t = Template::Empty)]
+    pub template: Template,
+    /// Overwrite an existing config file
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// The configurations `init` can create.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// Only comments explaining the format
+    Empty,
+    /// Prefixes for web searches and a fallback search engine
+    SearchEngines,
+    /// Documentation and code hosting shortcuts
+    Developer,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct InstallArgs {
     /// Port for the systemd socket to listen on
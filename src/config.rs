@@ -1,6 +1,18 @@
 use color_eyre::eyre::{Context, Result};
-use serde::Deserialize;
-use std::path::PathBuf;
+use once_cell::sync::OnceCell;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::include;
+use crate::matching::{self, Matcher, Template};
+use crate::vars::{self, Vars};
 
 fn default_case_sensitive() -> bool {
     false
@@ -12,25 +24,172 @@ fn default_tolerance() -> u32 {
     3
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Config {
     #[serde(rename = "match")]
     pub matcher: MatcherConfig,
+    /// Values for `${var.NAME}` in the matchers of this file and the files it
+    /// includes.
+    // After loading, these are the variables that were in effect.
+    #[serde(default)]
+    pub vars: Vars,
+    /// Named matchers that `ref` matchers can reuse.
+    #[serde(default)]
+    pub definitions: BTreeMap<String, Arc<MatcherConfig>>,
+    /// Redirect status for matchers that don't set their own.
+    #[serde(default)]
+    pub status: Option<RedirectStatus>,
+    /// Response mode for matchers that don't set their own.
+    #[serde(default)]
+    pub response: Option<ResponseMode>,
+    /// Where the daemon redirects inputs that no matcher accepts (e.g. a web
+    /// search), instead of answering with a 404 page.
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// The `fallback`, parsed on first use.
+    #[serde(skip)]
+    pub compiled_fallback: Compiled<Template>,
+    /// Inputs and what the config should do with them, checked by `test`.
+    #[serde(default)]
+    pub tests: Vec<TestCase>,
+    /// Glob patterns of files whose matchers are appended to this file's,
+    /// relative to this file.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// The files the configuration was loaded from, see [`load`].
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+}
+
+/// An embedded test case: what the config is expected to do with `input`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawTestCase")]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct TestCase {
+    pub input: String,
+    pub expected: Expected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    Url(String),
+    Text(String),
+    NoMatch,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(rename = "TestCase")]
+struct RawTestCase {
+    input: String,
+    /// The URL the config should redirect to.
+    #[serde(default)]
+    url: Option<String>,
+    /// The text the config should respond with.
+    #[serde(default)]
+    text: Option<String>,
+    /// Whether no matcher should accept the input.
+    #[serde(default, rename = "no-match")]
+    no_match: bool,
+}
+
+impl JsonSchema for TestCase {
+    fn schema_name() -> Cow<'static, str> {
+        "TestCase".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        RawTestCase::json_schema(generator)
+    }
+}
+
+impl TryFrom<RawTestCase> for TestCase {
+    type Error = String;
+
+    fn try_from(raw: RawTestCase) -> Result<Self, Self::Error> {
+        let url = raw
+            .url
+            .map(|url| vars::expand_active(&url, vars::Field::Literal))
+            .transpose()
+            .map_err(|err| format!("invalid test for `{}`: {err}", raw.input))?;
+        let expected = match (url, raw.text, raw.no_match) {
+            (Some(url), None, false) => Expected::Url(url),
+            (None, Some(text), false) => Expected::Text(text),
+            (None, None, true) => Expected::NoMatch,
+            _ => {
+                return Err(format!(
+                    "test for `{}` needs exactly one of `url`, `text` or `no-match: true`",
+                    raw.input
+                ));
+            }
+        };
+        Ok(TestCase {
+            input: raw.input,
+            expected,
+        })
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
+/// A node of the matcher tree.
+///
+/// The kind of matcher is determined by its discriminating key (e.g. `exact`),
+/// see [`MatcherKind`]. A list is a [`MatcherConfig::List`].
+#[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum MatcherConfig {
     Exact(ExactMatcherConfig),
     Prefix(PrefixMatcherConfig),
+    Suffix(SuffixMatcherConfig),
+    Contains(ContainsMatcherConfig),
     Fuzzy(FuzzyMatcherConfig),
+    Glob(GlobMatcherConfig),
     Regex(RegexMatcherConfig),
+    Query(QueryMatcherConfig),
+    Ref(RefMatcherConfig),
+    Keywords(KeywordsMatcherConfig),
+    Command(CommandMatcherConfig),
+    All(AllMatcherConfig),
+    Any(AnyMatcherConfig),
+    Not(NotMatcherConfig),
     List(Vec<MatcherConfig>),
 }
 
-#[derive(Debug, Deserialize)]
+/// The fields that every matcher with a target has, and so does an entry of a
+/// `keywords` matcher. `input` isn't one of them: `any` and `keywords` have it
+/// without a target, and `query` and keyword entries have a target without it.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct CommonConfig {
+    /// Shown on the landing page instead of the matcher or keyword itself.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// An input that is accepted, linked from the landing page.
+    #[serde(default)]
+    pub example: Option<String>,
+    /// More inputs that have to be accepted, checked by `test`.
+    #[serde(default)]
+    pub examples: Vec<String>,
+    #[serde(default, deserialize_with = "vars::deserialize_expanded_template")]
+    pub url: Option<String>,
+    /// Respond with this text instead of redirecting.
+    #[serde(default, deserialize_with = "vars::deserialize_expanded_template")]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub status: Option<RedirectStatus>,
+    #[serde(default)]
+    pub response: Option<ResponseMode>,
+    /// The `url` or `text`, parsed on first use.
+    #[serde(skip)]
+    pub compiled_target: Compiled<Template>,
+}
+
+/// Accepts inputs that are `exact`, e.g. `mail`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ExactMatcherConfig {
     pub exact: String,
@@ -38,40 +197,107 @@ pub struct ExactMatcherConfig {
     pub case_sensitive: bool,
     #[serde(default = "default_trim")]
     pub trim: bool,
-    #[serde(default)]
-    pub url: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
     #[serde(rename = "match")]
     #[serde(default)]
     pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Accepts inputs that start with `prefix`, e.g. `gh `.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct PrefixMatcherConfig {
     pub prefix: String,
     #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
     pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
     #[serde(default)]
-    pub url: Option<String>,
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// Accepts inputs that end with `suffix`, e.g. `.rs`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct SuffixMatcherConfig {
+    pub suffix: String,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub common: CommonConfig,
     #[serde(rename = "match")]
     #[serde(default)]
     pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// Accepts inputs that contain `contains`, e.g. `..`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ContainsMatcherConfig {
+    pub contains: String,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
+    #[serde(default)]
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Accepts inputs that are within `tolerance` edits of `fuzzy`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct FuzzyMatcherConfig {
     pub fuzzy: String,
     #[serde(default = "default_tolerance")]
     pub tolerance: u32,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
     #[serde(default)]
-    pub url: Option<String>,
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// Accepts inputs that match a glob pattern, e.g. `docs/*.md`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct GlobMatcherConfig {
+    /// A pattern with the wildcards `*`, `?` and `**`, and alternatives like
+    /// `{a,b}`.
+    pub glob: String,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub common: CommonConfig,
     #[serde(rename = "match")]
     #[serde(default)]
     pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+    #[serde(skip)]
+    pub compiled: Compiled<regex::Regex>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Accepts inputs that match a regular expression, e.g. `^(\w+)-(\d+)$`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct RegexMatcherConfig {
     pub regex: String,
@@ -80,27 +306,752 @@ pub struct RegexMatcherConfig {
     #[serde(rename = "match-with")]
     #[serde(default)]
     pub match_with: Option<String>,
+    /// The `match-with`, parsed on first use.
+    #[serde(skip)]
+    pub compiled_match_with: Compiled<Template>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
     #[serde(default)]
-    pub url: Option<String>,
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+    #[serde(skip)]
+    pub compiled: Compiled<regex::Regex>,
+}
+
+/// Accepts requests that have the query parameter `query`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct QueryMatcherConfig {
+    /// Name of the query parameter.
+    pub query: String,
+    /// Only match if the parameter has exactly this value.
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Only match if the parameter's value matches this regex.
+    #[serde(default, rename = "value-regex")]
+    pub value_regex: Option<String>,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub common: CommonConfig,
     #[serde(rename = "match")]
     #[serde(default)]
     pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(skip)]
+    pub compiled: Compiled<regex::Regex>,
+}
+
+/// Reuses one of the config's `definitions`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct RefMatcherConfig {
+    #[serde(rename = "ref")]
+    pub name: String,
+    /// The definition, linked once all files of the config are loaded.
+    #[serde(skip)]
+    pub target: Compiled<Arc<MatcherConfig>>,
+}
+
+/// Looks up its input in a table of keywords, e.g. `gh` or `mail`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct KeywordsMatcherConfig {
+    pub keywords: BTreeMap<String, KeywordConfig>,
+    /// Whether the input is a keyword followed by an argument, like
+    /// `gh rust`, instead of only a keyword.
+    #[serde(default)]
+    pub argument: bool,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+    /// The keywords and aliases (lowercase unless `case-sensitive`), mapped
+    /// to the keyword of their entry.
+    #[serde(skip)]
+    pub compiled: Compiled<HashMap<String, String>>,
+}
+
+/// An entry of a `keywords` matcher.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct KeywordConfig {
+    /// More keywords for this entry.
+    #[serde(default, deserialize_with = "vars::deserialize_expanded_list")]
+    pub aliases: Vec<String>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
+    #[serde(default)]
+    pub matcher: Option<Box<MatcherConfig>>,
+}
+
+/// Parses its input like a command line, e.g. `pr 1234 --repo infra`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct CommandMatcherConfig {
+    /// The first word of the input.
+    pub command: String,
+    #[serde(default = "default_case_sensitive", rename = "case-sensitive")]
+    pub case_sensitive: bool,
+    /// The positional arguments, in order.
+    #[serde(default)]
+    pub args: Vec<CommandArgConfig>,
+    /// The `--flags`, by name. Each one takes a value.
+    #[serde(default)]
+    pub flags: BTreeMap<String, CommandFlagConfig>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
+    #[serde(default)]
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// A positional argument of a `command` matcher.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct CommandArgConfig {
+    /// The placeholder the argument is bound to, e.g. `${number}`.
+    pub name: String,
+    #[serde(default)]
+    pub required: bool,
+    /// The value if the argument is missing.
+    #[serde(default, deserialize_with = "vars::deserialize_expanded")]
+    pub default: Option<String>,
+    /// Shown in the usage message.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A `--flag` of a `command` matcher, bound to the placeholder of its name.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct CommandFlagConfig {
+    #[serde(default)]
+    pub required: bool,
+    /// The value if the flag is missing.
+    #[serde(default, deserialize_with = "vars::deserialize_expanded")]
+    pub default: Option<String>,
+    /// Shown in the usage message.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Accepts its input if every one of its matchers does.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct AllMatcherConfig {
+    /// Conditions, which don't need a `url`, `text` or `match`. Without one
+    /// of its own, the matcher resolves the request like its last condition.
+    pub all: Vec<MatcherConfig>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
+    #[serde(default)]
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// Tries its matchers in order, like a list.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct AnyMatcherConfig {
+    pub any: Vec<MatcherConfig>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// Accepts its input if its matcher rejects it.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct NotMatcherConfig {
+    /// A condition, which doesn't need a `url`, `text` or `match`.
+    pub not: Box<MatcherConfig>,
+    #[serde(flatten)]
+    pub common: CommonConfig,
+    #[serde(rename = "match")]
+    #[serde(default)]
+    pub matcher: Option<Box<MatcherConfig>>,
+    #[serde(default)]
+    pub input: Option<InputSource>,
+}
+
+/// HTTP status of a redirect. Only redirect codes are allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(try_from = "u16", into = "u16")]
+#[schemars(extend("enum" = [301, 302, 303, 307, 308]))]
+pub struct RedirectStatus(u16);
+
+impl RedirectStatus {
+    pub const FOUND: RedirectStatus = RedirectStatus(302);
+
+    pub fn code(self) -> u16 {
+        self.0
+    }
+}
+
+impl TryFrom<u16> for RedirectStatus {
+    type Error = String;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        match code {
+            301 | 302 | 303 | 307 | 308 => Ok(RedirectStatus(code)),
+            _ => Err(format!(
+                "invalid redirect status {code}, expected one of 301, 302, 303, 307 or 308"
+            )),
+        }
+    }
+}
+
+impl From<RedirectStatus> for u16 {
+    fn from(status: RedirectStatus) -> Self {
+        status.0
+    }
+}
+
+/// How the daemon sends a matched URL to the browser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseMode {
+    /// Redirect right away.
+    #[default]
+    Redirect,
+    /// Show a page with the destination that the user has to confirm.
+    Interstitial,
+}
+
+/// Which part of the request a matcher works on, instead of the input it gets
+/// from its parent (see the `input` option).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(try_from = "String")]
+#[schemars(extend("pattern" = r"^(path|query|target|fragment|query\..+)$"))]
+pub enum InputSource {
+    /// The request path, without the query.
+    Path,
+    /// The query string as it was sent, without the leading `?`.
+    Query,
+    /// The path followed by the query, e.g. `search?q=rust`.
+    Target,
+    /// The fragment. Browsers don't send it, so it's only available to `apply`.
+    Fragment,
+    /// The decoded value of a single query parameter, written `query.NAME`.
+    Param(String),
+}
+
+impl TryFrom<String> for InputSource {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(match value.as_str() {
+            "path" => InputSource::Path,
+            "query" => InputSource::Query,
+            "target" => InputSource::Target,
+            "fragment" => InputSource::Fragment,
+            _ => match value.strip_prefix("query.") {
+                Some(name) if !name.is_empty() => InputSource::Param(name.to_string()),
+                _ => {
+                    return Err(format!(
+                        "unknown input `{value}`, expected one of `path`, `query`, `target`, `fragment` or `query.NAME`"
+                    ));
+                }
+            },
+        })
+    }
+}
+
+/// The kinds of matchers that are written as a map, identified by the key
+/// that only they have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatcherKind {
+    Exact,
+    Prefix,
+    Suffix,
+    Contains,
+    Fuzzy,
+    Glob,
+    Regex,
+    Query,
+    Ref,
+    Keywords,
+    Command,
+    All,
+    Any,
+    Not,
+}
+
+impl MatcherKind {
+    pub const ALL: [MatcherKind; 14] = [
+        MatcherKind::Exact,
+        MatcherKind::Prefix,
+        MatcherKind::Suffix,
+        MatcherKind::Contains,
+        MatcherKind::Fuzzy,
+        MatcherKind::Glob,
+        MatcherKind::Regex,
+        MatcherKind::Query,
+        MatcherKind::Ref,
+        MatcherKind::Keywords,
+        MatcherKind::Command,
+        MatcherKind::All,
+        MatcherKind::Any,
+        MatcherKind::Not,
+    ];
+
+    /// The discriminating key of this kind of matcher.
+    pub fn key(self) -> &'static str {
+        match self {
+            MatcherKind::Exact => "exact",
+            MatcherKind::Prefix => "prefix",
+            MatcherKind::Suffix => "suffix",
+            MatcherKind::Contains => "contains",
+            MatcherKind::Fuzzy => "fuzzy",
+            MatcherKind::Glob => "glob",
+            MatcherKind::Regex => "regex",
+            MatcherKind::Query => "query",
+            MatcherKind::Ref => "ref",
+            MatcherKind::Keywords => "keywords",
+            MatcherKind::Command => "command",
+            MatcherKind::All => "all",
+            MatcherKind::Any => "any",
+            MatcherKind::Not => "not",
+        }
+    }
+
+    /// Determines the kind of matcher from the keys of its map.
+    fn detect(fields: &serde_yaml::Mapping) -> Result<MatcherKind, String> {
+        let keys: Vec<&str> = fields
+            .keys()
+            .filter_map(serde_yaml::Value::as_str)
+            .collect();
+        let kinds: Vec<MatcherKind> = MatcherKind::ALL
+            .into_iter()
+            .filter(|kind| keys.contains(&kind.key()))
+            .collect();
+        match kinds[..] {
+            [kind] => Ok(kind),
+            [] => {
+                let mut msg = format!(
+                    "cannot tell which kind of matcher this is, expected one of the keys {}",
+                    MatcherKind::ALL
+                        .map(|kind| format!("`{}`", kind.key()))
+                        .join(", ")
+                );
+                // Most likely a typo in the discriminating key.
+                let suggestion = keys.iter().find_map(|key| {
+                    MatcherKind::ALL
+                        .into_iter()
+                        .find(|kind| strsim::levenshtein(key, kind.key()) <= 2)
+                        .map(|kind| (key, kind))
+                });
+                if let Some((key, kind)) = suggestion {
+                    msg.push_str(&format!(
+                        " (did you mean `{}` instead of `{key}`?)",
+                        kind.key()
+                    ));
+                }
+                Err(msg)
+            }
+            _ => Err(format!(
+                "ambiguous matcher, it has more than one of the keys {}",
+                kinds
+                    .iter()
+                    .map(|kind| format!("`{}`", kind.key()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    fn build(
+        self,
+        fields: serde_yaml::Mapping,
+        matcher: Option<Box<MatcherConfig>>,
+        nested: Option<Nested>,
+    ) -> Result<MatcherConfig, serde_yaml::Error> {
+        let fields = serde_yaml::Value::Mapping(fields);
+        Ok(match self {
+            MatcherKind::Exact => MatcherConfig::Exact(ExactMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Prefix => MatcherConfig::Prefix(PrefixMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Suffix => MatcherConfig::Suffix(SuffixMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Contains => MatcherConfig::Contains(ContainsMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Fuzzy => MatcherConfig::Fuzzy(FuzzyMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Glob => MatcherConfig::Glob(GlobMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Regex => MatcherConfig::Regex(RegexMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Query => MatcherConfig::Query(QueryMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::Ref if matcher.is_some() => {
+                return Err(de::Error::custom("unknown field `match`, expected `ref`"));
+            }
+            MatcherKind::Ref => MatcherConfig::Ref(serde_yaml::from_value(fields)?),
+            MatcherKind::Keywords if matcher.is_some() => {
+                return Err(de::Error::custom(
+                    "`match` belongs in an entry of `keywords`",
+                ));
+            }
+            MatcherKind::Keywords => {
+                let Some(Nested::Keywords(keywords)) = nested else {
+                    unreachable!("`keywords` is read in place");
+                };
+                MatcherConfig::Keywords(KeywordsMatcherConfig {
+                    keywords,
+                    ..serde_yaml::from_value(fields)?
+                })
+            }
+            MatcherKind::Command => MatcherConfig::Command(CommandMatcherConfig {
+                matcher,
+                ..serde_yaml::from_value(fields)?
+            }),
+            MatcherKind::All => {
+                let Some(Nested::Matchers(all)) = nested else {
+                    unreachable!("`all` is read in place");
+                };
+                MatcherConfig::All(AllMatcherConfig {
+                    all,
+                    matcher,
+                    ..serde_yaml::from_value(fields)?
+                })
+            }
+            MatcherKind::Any if matcher.is_some() => {
+                return Err(de::Error::custom(
+                    "`match` belongs in one of the matchers of `any`",
+                ));
+            }
+            MatcherKind::Any => {
+                let Some(Nested::Matchers(any)) = nested else {
+                    unreachable!("`any` is read in place");
+                };
+                MatcherConfig::Any(AnyMatcherConfig {
+                    any,
+                    ..serde_yaml::from_value(fields)?
+                })
+            }
+            MatcherKind::Not => {
+                let Some(Nested::Matcher(not)) = nested else {
+                    unreachable!("`not` is read in place");
+                };
+                MatcherConfig::Not(NotMatcherConfig {
+                    not,
+                    matcher,
+                    ..serde_yaml::from_value(fields)?
+                })
+            }
+        })
+    }
+}
+
+/// The matchers of an `all`, `any` or `not` matcher, or the entries of a
+/// `keywords` matcher, which are read in place rather than buffered with the
+/// other fields.
+enum Nested {
+    Matchers(Vec<MatcherConfig>),
+    Matcher(Box<MatcherConfig>),
+    Keywords(BTreeMap<String, KeywordConfig>),
+}
+
+thread_local! {
+    /// Whether the matchers being read are conditions of an `all` or `not`
+    /// matcher (or grouped in an `any` or a list there), which don't need a
+    /// `url`, `text` or `match`. The `match` of a condition resolves the
+    /// request, so it isn't one.
+    static CONDITIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Reads the matchers of `f` as conditions or not, see [`CONDITIONS`].
+fn conditions<T>(condition: bool, f: impl FnOnce() -> T) -> T {
+    let outer = CONDITIONS.replace(condition);
+    let result = f();
+    CONDITIONS.set(outer);
+    result
+}
+
+impl MatcherConfig {
+    /// Whether the matcher resolves the requests it accepts, rather than only
+    /// being able to serve as a condition.
+    fn has_target(&self) -> bool {
+        fn any(common: &CommonConfig, sub: &Option<Box<MatcherConfig>>) -> bool {
+            common.url.is_some() || common.text.is_some() || sub.is_some()
+        }
+        match self {
+            MatcherConfig::Exact(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Prefix(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Suffix(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Contains(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Fuzzy(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Glob(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Regex(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Query(cfg) => any(&cfg.common, &cfg.matcher),
+            // Entries of `keywords` are checked when it is compiled.
+            MatcherConfig::Ref(_) | MatcherConfig::Keywords(_) => true,
+            MatcherConfig::Command(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::All(cfg) => {
+                any(&cfg.common, &cfg.matcher)
+                    || cfg.all.last().is_some_and(MatcherConfig::has_target)
+            }
+            MatcherConfig::Not(cfg) => any(&cfg.common, &cfg.matcher),
+            MatcherConfig::Any(AnyMatcherConfig { any: list, .. }) | MatcherConfig::List(list) => {
+                list.iter().all(MatcherConfig::has_target)
+            }
+        }
+    }
+}
+
+impl fmt::Display for MatcherKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// A matcher is one of the matcher objects, told apart by their
+/// discriminating key, or a list of matchers.
+impl JsonSchema for MatcherConfig {
+    fn schema_name() -> Cow<'static, str> {
+        "MatcherConfig".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A matcher, or a list of matchers that are tried in order.",
+            "anyOf": [
+                generator.subschema_for::<ExactMatcherConfig>(),
+                generator.subschema_for::<PrefixMatcherConfig>(),
+                generator.subschema_for::<SuffixMatcherConfig>(),
+                generator.subschema_for::<ContainsMatcherConfig>(),
+                generator.subschema_for::<FuzzyMatcherConfig>(),
+                generator.subschema_for::<GlobMatcherConfig>(),
+                generator.subschema_for::<RegexMatcherConfig>(),
+                generator.subschema_for::<QueryMatcherConfig>(),
+                generator.subschema_for::<RefMatcherConfig>(),
+                generator.subschema_for::<KeywordsMatcherConfig>(),
+                generator.subschema_for::<CommandMatcherConfig>(),
+                generator.subschema_for::<AllMatcherConfig>(),
+                generator.subschema_for::<AnyMatcherConfig>(),
+                generator.subschema_for::<NotMatcherConfig>(),
+                {
+                    "type": "array",
+                    "items": generator.subschema_for::<MatcherConfig>(),
+                },
+            ],
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for MatcherConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MatcherVisitor)
+    }
+}
+
+/// Deserializes matchers, checking each one as soon as it is read so that
+/// errors point at the matcher that caused them.
+struct MatcherVisitor;
+
+impl<'de> Visitor<'de> for MatcherVisitor {
+    type Value = MatcherConfig;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a matcher or a list of matchers")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<MatcherConfig, A::Error> {
+        let mut list = Vec::new();
+        while let Some(matcher) = seq.next_element()? {
+            list.push(matcher);
+        }
+        Ok(MatcherConfig::List(list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<MatcherConfig, A::Error> {
+        // Sub matchers, including those of `all`, `any` and `not` and the
+        // entries of `keywords`, are deserialized right away rather than
+        // buffered, so that errors in them keep their own location.
+        let mut fields = serde_yaml::Mapping::new();
+        let mut matcher = None;
+        let mut nested = None;
+        while let Some(key) = map.next_key::<String>()? {
+            nested = match key.as_str() {
+                "match" => {
+                    matcher = Some(conditions(false, || map.next_value())?);
+                    continue;
+                }
+                "all" => Some(Nested::Matchers(conditions(true, || map.next_value())?)),
+                "any" => Some(Nested::Matchers(map.next_value()?)),
+                "not" => Some(Nested::Matcher(conditions(true, || map.next_value())?)),
+                "keywords" => Some(Nested::Keywords(conditions(false, || map.next_value())?)),
+                _ => {
+                    fields.insert(key.into(), map.next_value()?);
+                    continue;
+                }
+            };
+            // Stands in for the value, so that the kind is detected from the
+            // keys and `build` can fill in the rest of the fields.
+            let placeholder = match nested {
+                Some(Nested::Keywords(_)) => serde_yaml::Value::Mapping(Default::default()),
+                _ => serde_yaml::Value::Sequence(Vec::new()),
+            };
+            fields.insert(key.into(), placeholder);
+        }
+        let kind = MatcherKind::detect(&fields).map_err(de::Error::custom)?;
+        let invalid = |err: &dyn fmt::Display| {
+            de::Error::custom(format!("invalid `{kind}` matcher: {err:#}"))
+        };
+        for (key, field) in vars::MATCHER_KEYS {
+            if let Some(serde_yaml::Value::String(value)) = fields.get_mut(key) {
+                *value = vars::expand_active(value, field).map_err(|err| invalid(&err))?;
+            }
+        }
+        let cfg = kind
+            .build(fields, matcher, nested)
+            .map_err(|err| invalid(&err))?;
+        if !CONDITIONS.get() && !cfg.has_target() {
+            return Err(invalid(&match kind {
+                MatcherKind::All => {
+                    "it needs one of `url`, `text` or `match`, or a last matcher that has one"
+                }
+                _ => "it needs one of `url`, `text` or `match`",
+            }));
+        }
+        cfg.compile().map_err(|err| invalid(&err))?;
+        Ok(cfg)
+    }
+}
+
+/// State derived from a matcher's configuration, such as a compiled regex.
+///
+/// It is built once when the configuration is loaded and reused for every
+/// request. It doesn't take part in equality, so configs compare by what was
+/// written in the file.
+pub struct Compiled<T>(OnceCell<T>);
+
+impl<T> Compiled<T> {
+    pub fn get(&self) -> Option<&T> {
+        self.0.get()
+    }
+
+    pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<T, E>) -> Result<&T, E> {
+        self.0.get_or_try_init(init)
+    }
+}
+
+impl<T> Default for Compiled<T> {
+    fn default() -> Self {
+        Compiled(OnceCell::new())
+    }
+}
+
+impl<T> std::fmt::Debug for Compiled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.0.get().is_some() {
+            "compiled"
+        } else {
+            "pending"
+        };
+        f.write_str(state)
+    }
 }
 
+impl<T> PartialEq for Compiled<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Compiled<T> {}
+
 impl Config {
-    pub fn parse(cfg: &str) -> Result<Self, serde_yaml::Error> {
-        serde_yaml::from_str(cfg)
+    /// Parses the configuration and compiles its matcher tree.
+    ///
+    /// Each matcher is checked and compiled while it is read, so errors carry
+    /// the location of the offending matcher.
+    pub fn parse(cfg: &str) -> Result<Self> {
+        Config::parse_as(cfg, Format::Yaml)
+    }
+
+    /// Like [`Config::parse`], for a configuration in the given format.
+    pub fn parse_as(cfg: &str, format: Format) -> Result<Self> {
+        let cfg = Config::parse_fragment(cfg, format, &Vars::new())?;
+        matching::link(&cfg)?;
+        Ok(cfg)
+    }
+
+    /// Like [`Config::parse_as`], but leaves `ref` matchers unlinked since
+    /// they may refer to definitions in other files.
+    ///
+    /// The `overrides` (e.g. the variables of all the files loaded together)
+    /// take precedence over the variables the file declares itself.
+    pub fn parse_fragment(cfg: &str, format: Format, overrides: &Vars) -> Result<Self> {
+        let mut vars = vars::declared(cfg, format)?;
+        vars.extend(overrides.clone());
+        let mut cfg: Config = vars::scope(vars.clone(), || format.parse(cfg))?;
+        cfg.fallback = cfg
+            .fallback
+            .map(|fallback| vars::expand(&fallback, &vars, vars::Field::Template))
+            .transpose()
+            .wrap_err("invalid `fallback`")?;
+        cfg.fallback_template().wrap_err("invalid `fallback`")?;
+        cfg.vars = vars;
+        Ok(cfg)
+    }
+
+    /// The parsed `fallback`, if there is one.
+    pub fn fallback_template(&self) -> Result<Option<&Template>> {
+        let Some(fallback) = &self.fallback else {
+            return Ok(None);
+        };
+        self.compiled_fallback
+            .get_or_try_init(|| Template::parse(fallback))
+            .map(Some)
     }
 }
 
+/// The YAML configuration in `s`, see [`Config::parse`].
 impl std::str::FromStr for Config {
-    type Err = serde_yaml::Error;
+    type Err = color_eyre::eyre::Report;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        serde_yaml::from_str(s)
+        Config::parse(s)
     }
 }
 
 /// Determine the config file path.
+///
+/// Without a path on the command line, this is the first of `config.yml`,
+/// `config.toml` and `config.json` in the config directory that exists.
 pub fn config_path(cli: Option<PathBuf>) -> Result<PathBuf> {
     if let Some(p) = cli {
         return Ok(p);
@@ -109,9 +1060,113 @@ pub fn config_path(cli: Option<PathBuf>) -> Result<PathBuf> {
     let home = xdg
         .get_config_home()
         .ok_or_else(|| color_eyre::eyre::eyre!("missing home directory"))?;
-    Ok(home.join("config.yml"))
+    let existing = Format::ALL
+        .iter()
+        .map(|format| home.join(format!("config.{}", format.extension())))
+        .find(|path| path.exists());
+    Ok(existing.unwrap_or_else(|| home.join("config.yml")))
 }
 
+/// A file format that configurations can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Yaml, Format::Toml, Format::Json];
+
+    /// The format of the configuration file at `path`, by its extension.
+    /// Files with an unknown extension are YAML.
+    pub fn of(path: &Path) -> Format {
+        Format::from_extension(path).unwrap_or(Format::Yaml)
+    }
+
+    /// The format of `path` if its extension is one of a configuration file.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "yml" | "yaml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Yaml => "yml",
+            Format::Toml => "toml",
+            Format::Json => "json",
+        }
+    }
+
+    /// Deserializes `input`, with the location of the error if it fails.
+    pub fn parse<T: de::DeserializeOwned>(self, input: &str) -> Result<T, ParseError> {
+        match self {
+            Format::Yaml => serde_yaml::from_str(input).map_err(|err| {
+                let location = err.location().map(|l| (l.line(), l.column()));
+                ParseError::new(err.to_string(), location)
+            }),
+            Format::Toml => toml::from_str(input).map_err(|err| {
+                let location = err.span().map(|span| line_column(input, span.start));
+                ParseError::new(err.message().to_string(), location)
+            }),
+            Format::Json => serde_json::from_str(input).map_err(|err| {
+                let location = (err.line() > 0).then(|| (err.line(), err.column()));
+                ParseError::new(err.to_string(), location)
+            }),
+        }
+    }
+}
+
+/// The 1-based line and column of the byte `offset` in `input`.
+fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+/// An error in the contents of a configuration file, in any [`Format`].
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    /// The line and column the error is at, both 1-based.
+    pub location: Option<(usize, usize)>,
+}
+
+impl ParseError {
+    /// Builds an error from the message of a parser, which may end with the
+    /// location already.
+    fn new(message: String, location: Option<(usize, usize)>) -> Self {
+        let message = match location {
+            Some((line, column)) => {
+                let suffix = format!(" at line {line} column {column}");
+                message
+                    .strip_suffix(&suffix)
+                    .unwrap_or(&message)
+                    .to_string()
+            }
+            None => message,
+        };
+        ParseError { message, location }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some((line, column)) = self.location {
+            write!(f, " at line {line} column {column}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// Read the configuration file synchronously.
 pub fn read(config_path: &std::path::Path) -> Result<String> {
     std::fs::read_to_string(config_path).wrap_err_with(|| {
@@ -134,105 +1189,263 @@ pub async fn read_async(config_path: &std::path::Path) -> Result<String> {
         })
 }
 
+/// Read and parse the configuration file, along with the files it includes
+/// and the fragments in the `conf.d` directory next to it.
+pub fn load(config_path: &std::path::Path) -> Result<Config> {
+    include::load(config_path)
+}
+
+/// Context of errors in a single configuration file.
+#[derive(Debug)]
+pub struct InFile(pub PathBuf);
+
+impl fmt::Display for InFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse configuration file at {}",
+            self.0.display()
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn matcher(input: &str) -> MatcherConfig {
+        Config::parse(input).unwrap().matcher
+    }
+
     #[test]
     fn exact_defaults() {
         let input = "\
 match:\n  exact: Armadillo\n  url: https://google.com?q=$1\n";
         let cfg = Config::parse(input).unwrap();
-        let expected = Config {
-            matcher: MatcherConfig::Exact(ExactMatcherConfig {
-                exact: "Armadillo".into(),
-                case_sensitive: false,
-                trim: true,
-                url: Some("https://google.com?q=$1".into()),
-                matcher: None,
-            }),
+        let expected = "\
+match:\n  exact: Armadillo\n  case-sensitive: false\n  trim: true\n  url: https://google.com?q=$1\n";
+        assert_eq!(cfg, Config::parse(expected).unwrap());
+        assert_eq!(cfg.status, None);
+        assert_eq!(cfg.fallback, None);
+        assert!(cfg.tests.is_empty() && cfg.definitions.is_empty());
+        let MatcherConfig::Exact(exact) = &cfg.matcher else {
+            panic!("expected an exact matcher");
         };
-        assert_eq!(cfg, expected);
+        assert_eq!(exact.exact, "Armadillo");
+        assert_eq!(exact.common.url.as_deref(), Some("https://google.com?q=$1"));
     }
 
     #[test]
     fn list_matcher() {
         let input = "\
 match:\n- exact: Elephant\n  url: https://kagi.com?q=Elephant\n- exact: Lion\n  url: https://bing.com?q=Lion\n";
-        let cfg = Config::parse(input).unwrap();
-        let expected = Config {
-            matcher: MatcherConfig::List(vec![
-                MatcherConfig::Exact(ExactMatcherConfig {
-                    exact: "Elephant".into(),
-                    case_sensitive: false,
-                    trim: true,
-                    url: Some("https://kagi.com?q=Elephant".into()),
-                    matcher: None,
-                }),
-                MatcherConfig::Exact(ExactMatcherConfig {
-                    exact: "Lion".into(),
-                    case_sensitive: false,
-                    trim: true,
-                    url: Some("https://bing.com?q=Lion".into()),
-                    matcher: None,
-                }),
-            ]),
-        };
-        assert_eq!(cfg, expected);
+        let expected = MatcherConfig::List(vec![
+            matcher("match:\n  exact: Elephant\n  url: https://kagi.com?q=Elephant\n"),
+            matcher("match:\n  exact: Lion\n  url: https://bing.com?q=Lion\n"),
+        ]);
+        assert_eq!(matcher(input), expected);
     }
 
     #[test]
     fn regex_matcher() {
         let input = "\
 match:\n  regex: (\\w+)\\.txt$\n  url: https://file.drive/$1.txt\n";
-        let cfg = Config::parse(input).unwrap();
-        let expected = Config {
-            matcher: MatcherConfig::Regex(RegexMatcherConfig {
-                regex: "(\\w+)\\.txt$".into(),
-                case_sensitive: false,
-                match_with: None,
-                url: Some("https://file.drive/$1.txt".into()),
-                matcher: None,
-            }),
+        let expected = "\
+match:\n  regex: (\\w+)\\.txt$\n  case-sensitive: false\n  url: https://file.drive/$1.txt\n";
+        let MatcherConfig::Regex(regex) = matcher(input) else {
+            panic!("expected a regex matcher");
         };
-        assert_eq!(cfg, expected);
+        assert_eq!(regex.regex, "(\\w+)\\.txt$");
+        assert_eq!(MatcherConfig::Regex(regex), matcher(expected));
     }
 
     #[test]
     fn prefix_with_submatcher() {
         let input = "\
 match:\n  prefix: animals/\n  match:\n    exact: bear\n    url: https://bears.org\n";
-        let cfg = Config::parse(input).unwrap();
-        let expected = Config {
-            matcher: MatcherConfig::Prefix(PrefixMatcherConfig {
-                prefix: "animals/".into(),
-                case_sensitive: false,
-                url: None,
-                matcher: Some(Box::new(MatcherConfig::Exact(ExactMatcherConfig {
-                    exact: "bear".into(),
-                    case_sensitive: false,
-                    trim: true,
-                    url: Some("https://bears.org".into()),
-                    matcher: None,
-                }))),
-            }),
+        let MatcherConfig::Prefix(prefix) = matcher(input) else {
+            panic!("expected a prefix matcher");
         };
-        assert_eq!(cfg, expected);
+        assert_eq!(prefix.prefix, "animals/");
+        assert_eq!(prefix.common.url, None);
+        assert_eq!(
+            prefix.matcher.map(|sub| *sub),
+            Some(matcher("match:\n  exact: bear\n  url: https://bears.org\n"))
+        );
     }
 
     #[test]
     fn fuzzy_default_tolerance() {
         let input = "\
 match:\n  fuzzy: Elephant\n  url: https://heavy.animal\n";
+        let expected = "\
+match:\n  fuzzy: Elephant\n  tolerance: 3\n  url: https://heavy.animal\n";
+        assert_eq!(matcher(input), matcher(expected));
+    }
+
+    #[test]
+    fn unknown_key_names_matcher_kind() {
+        let input = "match:\n  exact: bear\n  ur: https://bears.org\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert!(
+            err.starts_with("match: invalid `exact` matcher: unknown field `ur`"),
+            "{err}"
+        );
+        assert!(err.ends_with("at line 2 column 3"), "{err}");
+    }
+
+    #[test]
+    fn misspelled_kind_suggests_key() {
+        let input =
+            "match:\n- exact: bear\n  url: https://bears.org\n- prefx: a/\n  url: https://a.org\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert!(
+            err.starts_with("match[1]: cannot tell which kind of matcher"),
+            "{err}"
+        );
+        assert!(
+            err.contains("did you mean `prefix` instead of `prefx`?"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn ambiguous_kind() {
+        let input = "match:\n  exact: bear\n  prefix: b\n  url: https://bears.org\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert!(
+            err.contains("ambiguous matcher, it has more than one of the keys `exact`, `prefix`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn matcher_without_url_or_match() {
+        let input = "match:\n  prefix: animals/\n  match:\n    exact: bear\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "match.match: invalid `exact` matcher: it needs one of `url`, `text` or `match` at line 4 column 5"
+        );
+    }
+
+    #[test]
+    fn status_and_response() {
+        let input =
+            "status: 301\nmatch:\n  exact: phone\n  text: '555'\n  response: interstitial\n";
         let cfg = Config::parse(input).unwrap();
-        let expected = Config {
-            matcher: MatcherConfig::Fuzzy(FuzzyMatcherConfig {
-                fuzzy: "Elephant".into(),
-                tolerance: 3,
-                url: Some("https://heavy.animal".into()),
-                matcher: None,
-            }),
+        assert_eq!(cfg.status, Some(RedirectStatus::try_from(301).unwrap()));
+        let MatcherConfig::Exact(exact) = cfg.matcher else {
+            panic!("expected an exact matcher");
+        };
+        assert_eq!(exact.common.text.as_deref(), Some("555"));
+        assert_eq!(exact.common.response, Some(ResponseMode::Interstitial));
+
+        let err = Config::parse("status: 200\nmatch:\n  exact: a\n  url: b\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid redirect status 200"), "{err}");
+
+        let err = Config::parse("match:\n  exact: a\n  url: b\n  text: c\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("use either `url` or `text`, not both"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn templates_are_parsed_when_loading() {
+        let input =
+            "fallback: https://example.com/$1\nmatch:\n  regex: a\n  match-with: $0\n  url: b\n";
+        let cfg = Config::parse(input).unwrap();
+        assert!(cfg.compiled_fallback.get().is_some());
+        let MatcherConfig::Regex(regex) = &cfg.matcher else {
+            panic!("expected a regex matcher");
         };
-        assert_eq!(cfg, expected);
+        assert!(regex.common.compiled_target.get().is_some());
+        assert!(regex.compiled_match_with.get().is_some());
+    }
+
+    #[test]
+    fn invalid_fallback() {
+        let input = "fallback: https://example.com/${1|nope}\nmatch:\n  exact: a\n  url: b\n";
+        let err = format!("{:#}", Config::parse(input).unwrap_err());
+        assert!(err.starts_with("invalid `fallback`: "), "{err}");
+    }
+
+    #[test]
+    fn vars_are_expanded_when_loading() {
+        let input = "vars:
+  host: jira.example
+  project: ops
+match:
+  prefix: ${var.project}-
+  url: https://${var.host}/browse/${var.project}-$2
+tests:
+- input: ops-1
+  url: https://${var.host}/browse/ops-1
+";
+        let cfg = Config::parse(input).unwrap();
+        let MatcherConfig::Prefix(prefix) = &cfg.matcher else {
+            panic!("expected a prefix matcher");
+        };
+        assert_eq!(prefix.prefix, "ops-");
+        assert_eq!(
+            prefix.common.url.as_deref(),
+            Some("https://jira.example/browse/ops-$2")
+        );
+        assert_eq!(
+            cfg.tests[0].expected,
+            Expected::Url("https://jira.example/browse/ops-1".into())
+        );
+
+        let cfg = Config::parse(
+            "vars:\n  sep: '-'\nmatch:\n  regex: (\\w+)_(\\w+)\n  match-with: $1${var.sep}$2\n  match:\n    exact: a-b\n    text: ${var.sep}\n",
+        )
+        .unwrap();
+        assert_eq!(cfg.matcher.apply("a_b").unwrap().as_deref(), Some("-"));
+
+        let err = Config::parse(&input.replace("${var.host}/browse", "${var.hots}/browse"))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "match: invalid `prefix` matcher: undefined variable `hots` at line 5 column 3"
+        );
+    }
+
+    #[test]
+    fn toml_and_json_formats() {
+        let yaml = "status: 307\nmatch:\n- exact: phone\n  text: '555'\n- prefix: gh/\n  url: https://github.com/$2\n";
+        let toml = "status = 307\n\n[[match]]\nexact = \"phone\"\ntext = \"555\"\n\n[[match]]\nprefix = \"gh/\"\nurl = \"https://github.com/$2\"\n";
+        let json = r#"{"status": 307, "match": [{"exact": "phone", "text": "555"}, {"prefix": "gh/", "url": "https://github.com/$2"}]}"#;
+        let expected = Config::parse(yaml).unwrap();
+        assert_eq!(Config::parse_as(toml, Format::Toml).unwrap(), expected);
+        assert_eq!(Config::parse_as(json, Format::Json).unwrap(), expected);
+
+        let err = Config::parse_as("[match]\nexact = \"a\"\n", Format::Toml).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid `exact` matcher: it needs one of `url`, `text` or `match` at line 1 column 1"
+        );
+    }
+
+    #[test]
+    fn format_by_extension() {
+        assert_eq!(Format::of(Path::new("config.yml")), Format::Yaml);
+        assert_eq!(Format::of(Path::new("team/a.toml")), Format::Toml);
+        assert_eq!(Format::of(Path::new("generated.json")), Format::Json);
+        assert_eq!(Format::of(Path::new("config")), Format::Yaml);
+        assert_eq!(Format::from_extension(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn unknown_input_source() {
+        let input = "match:\n  exact: bear\n  input: body\n  url: https://bears.org\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert!(err.contains("unknown input `body`"), "{err}");
     }
 }
@@ -1,59 +1,256 @@
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use axum::{Router, routing::get};
+use axum::{Json, Router, routing::get};
 use axum::{
-    extract::{Path, State},
-    http::{StatusCode, header},
-    response::{IntoResponse, Response},
+    extract::{Path, Query, RawQuery, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{Html, IntoResponse, Response},
 };
 use color_eyre::eyre::Result;
+use serde::Deserialize;
 use tracing::{info, instrument};
 
-use crate::{cli::DaemonArgs, config::Config, matching::Matcher, systemd};
+use crate::{
+    cli::DaemonArgs,
+    config::{Config, RedirectStatus, ResponseMode},
+    explain::Explanation,
+    html, init,
+    matching::{self, Context, Request, Resolution},
+    opensearch, shortcuts, systemd,
+    watch::LiveConfig,
+};
+
+/// How many similar shortcuts the 404 page suggests.
+const SUGGESTIONS: usize = 5;
+/// How many completions the browser gets while the user types.
+const COMPLETIONS: usize = 10;
 
 #[derive(Clone)]
 struct AppState {
-    config_path: PathBuf,
+    config: Arc<LiveConfig>,
+}
+
+/// Browsers never send the fragment, so a request is just path and query.
+fn request(path: &str, query: Option<String>) -> Request {
+    Request {
+        path: path.trim_start_matches('/').to_string(),
+        query,
+        fragment: None,
+    }
 }
 
 #[instrument(level = "info", skip(state))]
-async fn handler(State(state): State<AppState>, Path(path): Path<String>) -> Response {
-    let input = path.trim_start_matches('/');
+async fn handler(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+    RawQuery(query): RawQuery,
+) -> Response {
+    let cfg = match state.config.current() {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err).into_response();
+        }
+    };
+
+    let request = request(&path, query);
+    resolve(&cfg, request.clone()).unwrap_or_else(|| not_found(&cfg, request))
+}
 
-    // We re-read the configuration on every request for now
-    let cfg_str = match crate::config::read_async(&state.config_path).await {
-        Ok(s) => s,
+/// Redirects to the config's `fallback`, or serves a 404 page with similar
+/// shortcuts if it doesn't have one.
+fn not_found(cfg: &Config, request: Request) -> Response {
+    let fallback = match cfg.fallback_template() {
+        Ok(fallback) => fallback,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let Some(fallback) = fallback else {
+        let suggestions = shortcuts::suggestions(&cfg.matcher, &request.path, SUGGESTIONS);
+        let page = html::not_found(&request.target(), &suggestions);
+        return (StatusCode::NOT_FOUND, Html(page)).into_response();
+    };
+    let url = matching::render_fallback(fallback, &Context::new(request));
+    respond(
+        cfg,
+        Resolution::Redirect {
+            url,
+            status: None,
+            response: None,
+        },
+    )
+}
+
+/// Serves `/`, which shows the list of shortcuts unless a matcher accepts
+/// the empty path.
+#[instrument(level = "info", skip(state))]
+async fn root_handler(State(state): State<AppState>, RawQuery(query): RawQuery) -> Response {
+    let cfg = match state.config.current() {
+        Ok(cfg) => cfg,
         Err(err) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+            return (StatusCode::INTERNAL_SERVER_ERROR, err).into_response();
         }
     };
-    let cfg: Config = match Config::parse(&cfg_str) {
+
+    resolve(&cfg, request("", query)).unwrap_or_else(|| {
+        let shortcuts = shortcuts::collect(&cfg);
+        let engines = opensearch::engines(&cfg.matcher);
+        Html(html::index(&shortcuts, &engines)).into_response()
+    })
+}
+
+#[instrument(level = "info", skip(state))]
+async fn shortcuts_handler(State(state): State<AppState>) -> Response {
+    match state.config.current() {
+        Ok(cfg) => Json(shortcuts::collect(&cfg)).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+    }
+}
+
+/// Serves the OpenSearch description for all shortcuts.
+#[instrument(level = "info")]
+async fn opensearch_handler(headers: HeaderMap) -> Response {
+    opensearch_response(&opensearch::Engine::all(), &headers)
+}
+
+/// Serves the OpenSearch description for a single `prefix` matcher, at
+/// `/_opensearch/PREFIX.xml`.
+#[instrument(level = "info", skip(state))]
+async fn prefix_opensearch_handler(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let cfg = match state.config.current() {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err).into_response();
+        }
+    };
+    let engine = file
+        .strip_suffix(".xml")
+        .and_then(|prefix| opensearch::engine(&cfg.matcher, prefix));
+    match engine {
+        Some(engine) => opensearch_response(&engine, &headers),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn opensearch_response(engine: &opensearch::Engine, headers: &HeaderMap) -> Response {
+    (
+        [(header::CONTENT_TYPE, opensearch::CONTENT_TYPE)],
+        engine.description(&base_url(headers)),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct SuggestParams {
+    #[serde(default)]
+    q: String,
+    /// The prefix of the search engine that asks, see
+    /// [`opensearch::Engine`].
+    #[serde(default)]
+    prefix: String,
+}
+
+/// Serves OpenSearch suggestions for what the user has typed so far.
+#[instrument(level = "info", skip(state))]
+async fn suggest_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SuggestParams>,
+    headers: HeaderMap,
+) -> Response {
+    let cfg = match state.config.current() {
         Ok(cfg) => cfg,
         Err(err) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+            return (StatusCode::INTERNAL_SERVER_ERROR, err).into_response();
         }
     };
 
-    match cfg.matcher.apply(input) {
-        Ok(Some(url)) => (StatusCode::FOUND, [(header::LOCATION, url)]).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    let input = format!("{}{}", params.prefix, params.q);
+    let completions = shortcuts::completions(&cfg.matcher, &input, COMPLETIONS);
+    let suggestions =
+        opensearch::suggestions(&params.q, &params.prefix, &completions, &base_url(&headers));
+    (
+        [(header::CONTENT_TYPE, opensearch::SUGGESTIONS_CONTENT_TYPE)],
+        suggestions.to_string(),
+    )
+        .into_response()
+}
+
+/// The daemon's URL as the browser sees it. Browsers reach the daemon by the
+/// host they sent the request to, so that's what links have to use.
+fn base_url(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("localhost");
+    format!("http://{host}")
+}
+
+/// Evaluates the config for `request`. Returns `None` if no matcher accepts
+/// it.
+fn resolve(cfg: &Config, request: Request) -> Option<Response> {
+    let mut ctx = Context::new(request);
+    match matching::evaluate_request(&cfg.matcher, &mut ctx) {
+        Ok(None) => None,
+        Ok(Some(resolution)) => Some(respond(cfg, resolution)),
+        Err(err) => Some((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()),
     }
 }
 
-fn router(config_path: PathBuf) -> Router {
-    async fn root_handler(State(state): State<AppState>) -> Response {
-        handler(State(state), Path(String::new())).await
+/// Turns a resolution into a response, falling back to the config-wide
+/// `status` and `response` where the matcher doesn't set its own.
+fn respond(cfg: &Config, resolution: Resolution) -> Response {
+    match resolution {
+        Resolution::Redirect {
+            url,
+            status,
+            response,
+        } => match response.or(cfg.response).unwrap_or_default() {
+            ResponseMode::Redirect => {
+                let status = status.or(cfg.status).unwrap_or(RedirectStatus::FOUND);
+                let status = StatusCode::from_u16(status.code())
+                    .expect("redirect statuses are valid status codes");
+                (status, [(header::LOCATION, url)]).into_response()
+            }
+            ResponseMode::Interstitial => Html(html::interstitial(&url)).into_response(),
+        },
+        Resolution::Text { text } => {
+            ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response()
+        }
+        // Only conditions accept without resolving, and those are never at
+        // the top.
+        Resolution::Accepted {} => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[instrument(level = "info", skip(state))]
+async fn explain_handler(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+    RawQuery(query): RawQuery,
+) -> Response {
+    match state.config.current() {
+        Ok(cfg) => Json(Explanation::new(&cfg, request(&path, query))).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
     }
+}
 
+fn router(config: Arc<LiveConfig>) -> Router {
     Router::new()
         .route("/", get(root_handler))
         // `/{*path}` captures the entire rest of the request path, including
         // multiple segments. This is required to support URLs like
         // `/foo/bar` which would otherwise only match the first segment.
         .route("/{*path}", get(handler))
-        .with_state(AppState { config_path })
+        .route("/_explain/{*path}", get(explain_handler))
+        .route("/_shortcuts.json", get(shortcuts_handler))
+        .route("/_opensearch.xml", get(opensearch_handler))
+        .route("/_suggest", get(suggest_handler))
+        .route("/_opensearch/{*file}", get(prefix_opensearch_handler))
+        .with_state(AppState { config })
 }
 
 pub async fn serve_http(args: DaemonArgs, config_path: PathBuf) -> Result<()> {
@@ -94,7 +291,10 @@ pub async fn serve_http(args: DaemonArgs, config_path: PathBuf) -> Result<()> {
         tokio::net::TcpListener::bind(addr).await?
     };
 
-    let app = router(config_path);
+    init::ensure(&config_path);
+    let config = LiveConfig::load(config_path);
+    config.watch()?;
+    let app = router(config);
 
     // Notify systemd that we're ready (only in systemd mode)
     if args.systemd {
@@ -114,5 +314,7 @@ pub fn run(args: DaemonArgs, config_path: PathBuf) -> Result<()> {
 }
 
 pub fn test_router(config_path: PathBuf) -> Router {
-    router(config_path)
+    let config = LiveConfig::load(config_path);
+    config.watch().expect("watch config");
+    router(config)
 }
@@ -0,0 +1,104 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+use tracing::{info, instrument};
+
+use crate::{
+    cli::ExplainArgs,
+    config::Config,
+    matching::{self, Outcome, Request, Resolution, Step},
+};
+
+/// How an input was evaluated against a config.
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    pub input: String,
+    /// The resulting redirect or text, if any matcher accepted the input.
+    #[serde(flatten)]
+    pub resolution: Option<Resolution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub trace: Option<Step>,
+}
+
+impl Explanation {
+    pub fn new(cfg: &Config, request: Request) -> Self {
+        let input = request.target();
+        let (result, trace) = matching::explain(&cfg.matcher, request);
+        let (resolution, error) = match result {
+            Ok(resolution) => (resolution, None),
+            Err(err) => (None, Some(format!("{err:#}"))),
+        };
+        Explanation {
+            input,
+            resolution,
+            error,
+            trace,
+        }
+    }
+
+    /// Renders the trace as an indented tree, one matcher per line.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if let Some(trace) = &self.trace {
+            render_step(trace, 0, &mut out);
+        }
+        match (&self.resolution, &self.error) {
+            (Some(resolution), _) => writeln!(out, "result: {}", resolution.value()),
+            (None, Some(error)) => writeln!(out, "result: error: {error}"),
+            (None, None) => writeln!(out, "result: no match"),
+        }
+        .expect("writing to a string cannot fail");
+        out
+    }
+}
+
+fn render_step(step: &Step, depth: usize, out: &mut String) {
+    let outcome = match &step.outcome {
+        Outcome::Matched { resolution } => resolution.to_string(),
+        Outcome::Delegated {
+            resolution: Resolution::Redirect { url, .. },
+        } => format!("sub matcher redirects to {url}"),
+        Outcome::Delegated {
+            resolution: Resolution::Text { text },
+        } => format!("sub matcher responds with `{text}`"),
+        Outcome::Delegated {
+            resolution: Resolution::Accepted {},
+        } => "sub matcher accepts the input".to_string(),
+        Outcome::NoMatch { reason } => format!("no match: {reason}"),
+        Outcome::Error { message } => format!("error: {message}"),
+    };
+    writeln!(
+        out,
+        "{:indent$}{}: {} on `{}` => {outcome}",
+        "",
+        step.path,
+        step.matcher,
+        step.input,
+        indent = depth * 2
+    )
+    .expect("writing to a string cannot fail");
+    for child in &step.steps {
+        render_step(child, depth + 1, out);
+    }
+}
+
+#[instrument(level = "debug", skip(args, config_path))]
+pub fn run(args: ExplainArgs, config_path: PathBuf) -> Result<()> {
+    info!(input = ?args.input, ?config_path, "explain start");
+
+    let cfg = crate::config::load(&config_path)?;
+    let explanation = Explanation::new(&cfg, Request::from_input(&args.input));
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+    } else {
+        print!("{}", explanation.render());
+    }
+
+    if explanation.resolution.is_none() {
+        std::process::exit(2);
+    }
+    Ok(())
+}
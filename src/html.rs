@@ -0,0 +1,197 @@
+//! The few HTML pages the daemon serves.
+
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+
+use crate::opensearch::{self, Engine};
+use crate::shortcuts::Shortcut;
+
+/// Characters that have to be encoded in a link to a shortcut.
+pub(crate) const LINK: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Escapes text for use in HTML content and quoted attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Wraps `body` in a minimal HTML document. `head` is added to the
+/// document's head as is.
+fn page(title: &str, head: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n\
+         {head}\
+         </head>\n\
+         <body>\n{body}</body>\n\
+         </html>\n",
+        escape(title)
+    )
+}
+
+/// A page that shows where a shortcut leads and lets the user decide whether
+/// to go there.
+///
+/// Only web addresses are linked. Anything else (e.g. `javascript:`) is only
+/// shown.
+pub fn interstitial(url: &str) -> String {
+    let web = url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    let url = escape(url);
+    let action = if web {
+        format!("<p><a href=\"{url}\" rel=\"noreferrer\">Continue</a></p>\n")
+    } else {
+        "<p>It is not a web address, so it can't be opened from here.</p>\n".to_string()
+    };
+    page(
+        "Leaving shortcut-catapult",
+        "",
+        &format!(
+            "<p>This shortcut leads to</p>\n\
+             <p><code>{url}</code></p>\n\
+             {action}"
+        ),
+    )
+}
+
+/// The landing page, listing every shortcut. Browsers offer to add the
+/// `engines` as search engines.
+pub fn index(shortcuts: &[Shortcut], engines: &[Engine]) -> String {
+    let mut head = String::new();
+    for engine in engines {
+        head.push_str(&format!(
+            "<link rel=\"search\" type=\"{}\" title=\"{}\" href=\"{}\">\n",
+            opensearch::CONTENT_TYPE,
+            escape(&engine.name),
+            escape(&engine.path())
+        ));
+    }
+
+    let mut rows = String::new();
+    for shortcut in shortcuts {
+        let example = match &shortcut.example {
+            Some(example) => format!(
+                "<a href=\"/{}\"><code>{}</code></a>",
+                escape(&utf8_percent_encode(example, LINK).to_string()),
+                escape(example)
+            ),
+            None => String::new(),
+        };
+        let target = match (&shortcut.url, &shortcut.text) {
+            (Some(url), _) => format!("<code>{}</code>", escape(url)),
+            (None, Some(text)) => format!("text <code>{}</code>", escape(text)),
+            (None, None) => String::new(),
+        };
+        rows.push_str(&format!(
+            "<tr>\n\
+             <td>{}</td>\n\
+             <td>{}</td>\n\
+             <td>{example}</td>\n\
+             <td>{target}</td>\n\
+             </tr>\n",
+            escape(&shortcut.title()),
+            escape(shortcut.description.as_deref().unwrap_or_default()),
+        ));
+    }
+    page(
+        "Shortcuts",
+        &head,
+        &format!(
+            "<h1>Shortcuts</h1>\n\
+             <table>\n\
+             <tr><th>Shortcut</th><th>Description</th><th>Example</th><th>Target</th></tr>\n\
+             {rows}\
+             </table>\n"
+        ),
+    )
+}
+
+/// The page for inputs that no matcher accepts, linking to similar
+/// shortcuts.
+pub fn not_found(input: &str, suggestions: &[String]) -> String {
+    let mut body = format!("<h1>No shortcut for <code>{}</code></h1>\n", escape(input));
+    if !suggestions.is_empty() {
+        body.push_str("<p>Did you mean</p>\n<ul>\n");
+        for suggestion in suggestions {
+            let href = utf8_percent_encode(suggestion, LINK);
+            body.push_str(&format!(
+                "<li><a href=\"/{}\">{}</a></li>\n",
+                escape(&href.to_string()),
+                escape(suggestion)
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str("<p><a href=\"/\">All shortcuts</a></p>\n");
+    page("No shortcut found", "", &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x?a=1&b='2'">"#),
+            "&lt;a href=&quot;x?a=1&amp;b=&#39;2&#39;&quot;&gt;"
+        );
+    }
+
+    #[test]
+    fn index_encodes_example_links() {
+        let shortcut = Shortcut {
+            path: "match".to_string(),
+            matchers: vec!["prefix `s/`".to_string()],
+            name: None,
+            description: None,
+            example: Some("s/c# & <b>?".to_string()),
+            examples: Vec::new(),
+            url: None,
+            text: None,
+        };
+        let html = index(&[shortcut], &[]);
+        assert!(
+            html.contains(
+                r#"<a href="/s/c%23%20&amp;%20%3Cb%3E%3F"><code>s/c# &amp; &lt;b&gt;?</code></a>"#
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn interstitial_escapes_url() {
+        let html = interstitial("https://example.com/?q=<b>");
+        assert!(html.contains(r#"href="https://example.com/?q=&lt;b&gt;""#));
+    }
+
+    #[test]
+    fn interstitial_only_links_web_addresses() {
+        let html = interstitial("javascript:alert(1)");
+        assert!(!html.contains("href"), "{html}");
+        assert!(html.contains("<code>javascript:alert(1)</code>"), "{html}");
+        assert!(interstitial("http://example.com").contains(r#"href="http://example.com""#));
+    }
+}
@@ -0,0 +1,330 @@
+//! Loading a configuration that is split across several files.
+//!
+//! The matchers of included files and `conf.d` fragments are appended to the
+//! top-level list matcher of the main file, so the main file's own matchers
+//! come first and win. Variables go the other way: the files are read in the
+//! same order, the main file, its includes and then `conf.d`, and a variable
+//! declared later overrides an earlier one in every file.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::config::{self, Config, Format, InFile, MatcherConfig};
+use crate::matching;
+use crate::vars::{self, Vars};
+
+/// Loads the configuration at `path`, its includes and the fragments in the
+/// `conf.d` directory next to it.
+pub fn load(path: &Path) -> Result<Config> {
+    let mut loader = Loader::default();
+    let fragments = conf_d(path)?;
+    let mut vars = Vars::new();
+    for file in std::iter::once(path).chain(fragments.iter().map(PathBuf::as_path)) {
+        loader.declare(file, &mut vars)?;
+    }
+    let mut cfg = loader.load(path, &vars)?;
+    for fragment in fragments {
+        let other = loader.load(&fragment, &vars)?;
+        merge(&mut cfg, other, &fragment)?;
+    }
+    cfg.sources = loader.sources;
+    matching::link(&cfg)?;
+    Ok(cfg)
+}
+
+/// The directory with configuration fragments that belongs to the main
+/// configuration file at `path`.
+pub fn conf_d_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new(".")).join("conf.d")
+}
+
+/// The configuration files (`*.yml`, `*.toml`, …) in the `conf.d` directory,
+/// sorted by name.
+fn conf_d(path: &Path) -> Result<Vec<PathBuf>> {
+    let dir = conf_d_dir(path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = expand(&dir, "*")?;
+    files.retain(|file| Format::from_extension(file).is_some());
+    Ok(files)
+}
+
+#[derive(Default)]
+struct Loader {
+    /// The files that are being loaded, outermost first, to detect cycles.
+    stack: Vec<PathBuf>,
+    sources: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Adds the variables declared by the file at `path` and the files it
+    /// includes to `vars`, in the order they are loaded.
+    fn declare(&mut self, path: &Path, vars: &mut Vars) -> Result<()> {
+        #[derive(Deserialize)]
+        struct Header {
+            #[serde(default)]
+            include: Vec<String>,
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.stack.contains(&canonical) {
+            // The cycle is reported when the files are loaded.
+            return Ok(());
+        }
+        let cfg_str = config::read(path)?;
+        let format = Format::of(path);
+        let declared = vars::declared(&cfg_str, format);
+        vars.extend(declared.wrap_err_with(|| InFile(path.to_path_buf()))?);
+        let header: Header = format
+            .parse(&cfg_str)
+            .wrap_err_with(|| InFile(path.to_path_buf()))?;
+
+        self.stack.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for pattern in header.include {
+            let files = expand(dir, &pattern)
+                .wrap_err_with(|| format!("invalid include `{pattern}` in {}", path.display()))?;
+            for file in files {
+                self.declare(&file, vars)
+                    .wrap_err_with(|| format!("included from {}", path.display()))?;
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Loads the file at `path` and its includes, with the `vars` of all the
+    /// files, see [`Loader::declare`].
+    fn load(&mut self, path: &Path, vars: &Vars) -> Result<Config> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let cycle: Vec<_> = self.stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect();
+            bail!("include cycle: {}", cycle.join(" -> "));
+        }
+
+        let cfg_str = config::read(path)?;
+        let mut cfg = Config::parse_fragment(&cfg_str, Format::of(path), vars)
+            .wrap_err_with(|| InFile(path.to_path_buf()))?;
+        self.sources.push(path.to_path_buf());
+
+        self.stack.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for pattern in cfg.include.clone() {
+            let files = expand(dir, &pattern)
+                .wrap_err_with(|| format!("invalid include `{pattern}` in {}", path.display()))?;
+            for file in files {
+                let other = self
+                    .load(&file, vars)
+                    .wrap_err_with(|| format!("included from {}", path.display()))?;
+                merge(&mut cfg, other, &file)?;
+            }
+        }
+        self.stack.pop();
+        Ok(cfg)
+    }
+}
+
+/// The files matching `pattern` relative to `dir`, sorted. A pattern without
+/// wildcards is returned as is, so that a missing file is reported when it's
+/// read.
+fn expand(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    if glob::Pattern::escape(pattern) == pattern {
+        return Ok(vec![dir.join(pattern)]);
+    }
+    let pattern = if Path::new(pattern).is_absolute() {
+        pattern.to_string()
+    } else {
+        let dir = glob::Pattern::escape(&dir.to_string_lossy());
+        format!("{dir}/{pattern}")
+    };
+    let mut files = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+    files.sort();
+    Ok(files)
+}
+
+/// Appends the matchers and tests of a fragment loaded from `path`.
+fn merge(cfg: &mut Config, fragment: Config, path: &Path) -> Result<()> {
+    let settings = [
+        ("status", fragment.status.is_some()),
+        ("response", fragment.response.is_some()),
+        ("fallback", fragment.fallback.is_some()),
+    ];
+    if let Some((key, _)) = settings.iter().find(|(_, set)| *set) {
+        bail!(
+            "`{key}` can only be set in the main configuration file, not in {}",
+            path.display()
+        );
+    }
+
+    let mut matchers = match std::mem::replace(&mut cfg.matcher, MatcherConfig::List(Vec::new())) {
+        MatcherConfig::List(list) => list,
+        matcher => vec![matcher],
+    };
+    match fragment.matcher {
+        MatcherConfig::List(list) => matchers.extend(list),
+        matcher => matchers.push(matcher),
+    }
+    cfg.matcher = MatcherConfig::List(matchers);
+    cfg.tests.extend(fragment.tests);
+    for (name, definition) in fragment.definitions {
+        if cfg.definitions.contains_key(&name) {
+            bail!(
+                "definition `{name}` in {} is already defined in another file",
+                path.display()
+            );
+        }
+        cfg.definitions.insert(name, definition);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::Matcher;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    fn describe(cfg: &Config) -> Vec<String> {
+        match &cfg.matcher {
+            MatcherConfig::List(list) => list.iter().map(MatcherConfig::describe).collect(),
+            matcher => vec![matcher.describe()],
+        }
+    }
+
+    #[test]
+    fn includes_and_conf_d_are_appended_in_order() {
+        let dir = TempDir::new().unwrap();
+        dir.child("config.yml")
+            .write_str(
+                "include: [team/*.yml]\nmatch:\n  exact: mine\n  url: https://mine.example\n",
+            )
+            .unwrap();
+        dir.child("team/b.yml")
+            .write_str("match:\n  exact: b\n  url: https://b.example\n")
+            .unwrap();
+        dir.child("team/a.yml")
+            .write_str("match:\n- exact: a\n  url: https://a.example\ntests:\n- input: a\n  url: https://a.example\n")
+            .unwrap();
+        dir.child("conf.d/10-extra.yml")
+            .write_str("match:\n  exact: extra\n  url: https://extra.example\n")
+            .unwrap();
+        dir.child("conf.d/ignored.txt")
+            .write_str("nonsense")
+            .unwrap();
+
+        let cfg = load(&dir.child("config.yml")).unwrap();
+        assert_eq!(
+            describe(&cfg),
+            ["exact `mine`", "exact `a`", "exact `b`", "exact `extra`"]
+        );
+        assert_eq!(cfg.tests.len(), 1);
+        assert_eq!(cfg.sources.len(), 4);
+    }
+
+    #[test]
+    fn files_can_mix_formats() {
+        let dir = TempDir::new().unwrap();
+        dir.child("config.toml")
+            .write_str("include = [\"team.json\"]\n\n[match]\nexact = \"mine\"\nurl = \"https://mine.example\"\n")
+            .unwrap();
+        dir.child("team.json")
+            .write_str(r#"{"match": {"exact": "team", "url": "https://team.example"}}"#)
+            .unwrap();
+        dir.child("conf.d/extra.yml")
+            .write_str("match:\n  exact: extra\n  url: https://extra.example\n")
+            .unwrap();
+
+        let cfg = load(&dir.child("config.toml")).unwrap();
+        assert_eq!(
+            describe(&cfg),
+            ["exact `mine`", "exact `team`", "exact `extra`"]
+        );
+    }
+
+    #[test]
+    fn later_files_override_vars() {
+        let dir = TempDir::new().unwrap();
+        dir.child("config.yml")
+            .write_str("vars:\n  host: main.example\n  path: main\ninclude: [team.yml]\nmatch:\n  exact: main\n  url: https://${var.host}/${var.path}\n")
+            .unwrap();
+        dir.child("team.yml")
+            .write_str("vars:\n  host: team.example\n  path: wiki\nmatch:\n  exact: wiki\n  url: https://${var.host}/${var.path}\n")
+            .unwrap();
+        let config = dir.child("config.yml");
+
+        let cfg = load(&config).unwrap();
+        let apply = |cfg: &Config, input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply(&cfg, "main").as_deref(),
+            Some("https://team.example/wiki")
+        );
+
+        dir.child("conf.d/personal.yml")
+            .write_str("vars:\n  host: personal.example\nmatch:\n  exact: extra\n  url: https://${var.host}/extra\n")
+            .unwrap();
+        let cfg = load(&config).unwrap();
+        assert_eq!(
+            apply(&cfg, "main").as_deref(),
+            Some("https://personal.example/wiki")
+        );
+        assert_eq!(
+            apply(&cfg, "wiki").as_deref(),
+            Some("https://personal.example/wiki")
+        );
+        assert_eq!(
+            apply(&cfg, "extra").as_deref(),
+            Some("https://personal.example/extra")
+        );
+        assert_eq!(cfg.vars["host"], "personal.example");
+    }
+
+    #[test]
+    fn include_cycle_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        dir.child("config.yml")
+            .write_str("include: [other.yml]\nmatch: []\n")
+            .unwrap();
+        dir.child("other.yml")
+            .write_str("include: [config.yml]\nmatch: []\n")
+            .unwrap();
+
+        let err = format!("{:#}", load(&dir.child("config.yml")).unwrap_err());
+        assert!(err.contains("include cycle: "), "{err}");
+        assert!(err.contains("config.yml -> "), "{err}");
+    }
+
+    #[test]
+    fn errors_name_the_included_file() {
+        let dir = TempDir::new().unwrap();
+        dir.child("config.yml")
+            .write_str("include: [team.yml]\nmatch: []\n")
+            .unwrap();
+        dir.child("team.yml")
+            .write_str("match:\n  regex: (open\n  url: https://example.com\n")
+            .unwrap();
+
+        let err = load(&dir.child("config.yml")).unwrap_err();
+        let file = err.downcast_ref::<InFile>().expect("error names a file");
+        assert!(file.0.ends_with("team.yml"), "{err:#}");
+        let err = format!("{err:#}");
+        assert!(err.starts_with("included from "), "{err}");
+        assert!(err.contains("invalid regex `(open`"), "{err}");
+
+        dir.child("team.yml")
+            .write_str("status: 301\nmatch: []\n")
+            .unwrap();
+        let err = format!("{:#}", load(&dir.child("config.yml")).unwrap_err());
+        assert!(
+            err.contains("`status` can only be set in the main configuration file"),
+            "{err}"
+        );
+    }
+}
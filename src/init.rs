@@ -0,0 +1,178 @@
+//! Creating a first configuration file from a template.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Context, Result, bail};
+use tracing::{info, instrument, warn};
+
+use crate::cli::{InitArgs, Template};
+use crate::config::Format;
+
+const EMPTY_TEMPLATE: &str = r#"# Configuration of shortcut-catapult. The README describes all matchers, and
+# `shortcut-catapult validate` checks this file for errors.
+#
+# `match` is a list of matchers that are tried in order. The first one that
+# accepts the input decides where to go, for example:
+#
+# - exact: mail
+#   url: https://mail.example.com
+# - prefix: "gh "
+#   url: https://github.com/search?q=$2
+match: []
+
+# Inputs and where they should lead, checked by `shortcut-catapult test`.
+tests: []
+"#;
+
+const SEARCH_ENGINES_TEMPLATE: &str = r#"# Configuration of shortcut-catapult. The README describes all matchers, and
+# `shortcut-catapult validate` checks this file for errors.
+#
+# Type a short prefix followed by a search term, e.g. `w rust`. Anything else
+# is searched with DuckDuckGo.
+match:
+- prefix: "w "
+  name: Wikipedia
+  example: w rust
+  url: https://en.wikipedia.org/wiki/Special:Search?search=$2
+- prefix: "yt "
+  name: YouTube
+  example: yt rust conf
+  url: https://www.youtube.com/results?search_query=$2
+- prefix: "maps "
+  name: OpenStreetMap
+  example: maps berlin
+  url: https://www.openstreetmap.org/search?query=$2
+- prefix: "ddg "
+  name: DuckDuckGo
+  example: ddg rust
+  url: https://duckduckgo.com/?q=$2
+
+# Where inputs go that no matcher accepts. `$1` is the whole input.
+fallback: https://duckduckgo.com/?q=$1
+
+tests:
+- input: w rust
+  url: https://en.wikipedia.org/wiki/Special:Search?search=rust
+"#;
+
+const DEVELOPER_TEMPLATE: &str = r#"# Configuration of shortcut-catapult. The README describes all matchers, and
+# `shortcut-catapult validate` checks this file for errors.
+#
+# Shortcuts for documentation and code hosting. Adjust `vars` to your own
+# GitHub account.
+vars:
+  github_user: octocat
+
+match:
+- prefix: "gh "
+  name: GitHub search
+  example: gh shortcut-catapult
+  url: https://github.com/search?q=$2
+- regex: ^gh/([\w.-]+)/([\w.-]+)/(\d+)$
+  name: GitHub issue or pull request
+  example: gh/rust-lang/rust/1
+  url: https://github.com/$1/$2/issues/$3
+- prefix: gh/
+  name: GitHub repository
+  example: gh/rust-lang/rust
+  url: https://github.com/$2
+- exact: me
+  name: My GitHub profile
+  url: https://github.com/${var.github_user}
+- prefix: "rs "
+  name: Rust standard library
+  example: rs vec
+  url: https://doc.rust-lang.org/std/?search=$2
+- prefix: "docs "
+  name: docs.rs
+  example: docs serde
+  url: https://docs.rs/$2
+- prefix: "crate "
+  name: crates.io
+  example: crate tokio
+  url: https://crates.io/search?q=$2
+- prefix: "mdn "
+  name: MDN Web Docs
+  example: mdn flexbox
+  url: https://developer.mozilla.org/search?q=$2
+- prefix: "so "
+  name: Stack Overflow
+  example: so borrow checker
+  url: https://stackoverflow.com/search?q=$2
+
+tests:
+- input: gh/rust-lang/rust/1
+  url: https://github.com/rust-lang/rust/issues/1
+- input: docs serde
+  url: https://docs.rs/serde
+"#;
+
+/// The contents of a configuration file created from `template`.
+pub fn contents(template: Template) -> &'static str {
+    match template {
+        Template::Empty => EMPTY_TEMPLATE,
+        Template::SearchEngines => SEARCH_ENGINES_TEMPLATE,
+        Template::Developer => DEVELOPER_TEMPLATE,
+    }
+}
+
+/// Writes the `template` to `path`, creating its directory.
+fn write(path: &Path, template: Template) -> Result<()> {
+    if Format::of(path) != Format::Yaml {
+        bail!(
+            "templates are YAML, but {} is not a YAML file",
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("failed to create directory {}", dir.display()))?;
+    }
+    std::fs::write(path, contents(template))
+        .wrap_err_with(|| format!("failed to write configuration file {}", path.display()))
+}
+
+/// Creates a configuration file from the empty template if there is none at
+/// `path` yet, so that the daemon has something to serve on its first run.
+pub fn ensure(path: &Path) {
+    if path.exists() {
+        return;
+    }
+    match write(path, Template::Empty) {
+        Ok(()) => info!(path = %path.display(), "created configuration file"),
+        Err(err) => warn!("no configuration file and creating one failed: {err:#}"),
+    }
+}
+
+#[instrument(level = "debug", skip(args, config_path))]
+pub fn run(args: InitArgs, config_path: PathBuf) -> Result<()> {
+    let path = args.file.unwrap_or(config_path);
+    if path.exists() && !args.force {
+        bail!(
+            "{} already exists, use --force to overwrite it",
+            path.display()
+        );
+    }
+    write(&path, args.template)?;
+    println!("created {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::test::run_tests;
+
+    #[test]
+    fn templates_are_valid_and_pass_their_tests() {
+        for template in Template::value_variants() {
+            let cfg = Config::parse(contents(*template))
+                .unwrap_or_else(|err| panic!("{template:?}: {err:#}"));
+            let report = run_tests(&cfg);
+            assert!(report.failures.is_empty(), "{template:?}: {report}");
+        }
+    }
+}
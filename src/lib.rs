@@ -5,8 +5,19 @@ pub mod apply;
 pub mod cli;
 pub mod config;
 pub mod daemon;
+pub mod explain;
+pub mod html;
+pub mod include;
+pub mod init;
 pub mod matching;
+pub mod opensearch;
+pub mod schema;
+pub mod shortcuts;
 pub mod systemd;
+pub mod test;
+pub mod validate;
+pub mod vars;
+pub mod watch;
 
 /// Initialize error handling and tracing.
 ///
@@ -35,13 +46,3 @@ pub fn init(level: Option<tracing::Level>) -> Result<()> {
     })
     .map(|_| ())
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn init_ok() {
-        init(None).expect("init should not error");
-    }
-}
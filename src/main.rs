@@ -5,7 +5,7 @@ use tracing::instrument;
 use shortcut_catapult::{
     apply,
     cli::{Cli, Commands},
-    config, daemon, systemd,
+    config, daemon, explain, init, schema, systemd, test, validate,
 };
 
 #[instrument(level = "trace")]
@@ -31,6 +31,11 @@ fn run() -> Result<()> {
     match cli.command {
         Commands::Daemon(args) => daemon::run(args, config_path)?,
         Commands::Apply(args) => apply::run(args, config_path)?,
+        Commands::Validate(args) => validate::run(args, config_path)?,
+        Commands::Explain(args) => explain::run(args, config_path)?,
+        Commands::Test(args) => test::run(args, config_path)?,
+        Commands::Schema(args) => schema::run(args)?,
+        Commands::Init(args) => init::run(args, config_path)?,
         Commands::Install(args) => systemd::install(args)?,
         Commands::Uninstall(args) => systemd::uninstall(args)?,
     }
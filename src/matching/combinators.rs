@@ -0,0 +1,229 @@
+use eyre::{Result, bail};
+use tracing::instrument;
+
+use super::resolution::Target;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::{AllMatcherConfig, AnyMatcherConfig, MatcherConfig, NotMatcherConfig};
+
+/// Resolves the request with the `url`, `text` or `match` of an `all` or
+/// `not` matcher that accepted `input`, which is forwarded unchanged.
+fn resolve(
+    target: Target<'_>,
+    matcher: &Option<Box<MatcherConfig>>,
+    input: &str,
+    ctx: &mut Context,
+) -> Result<Option<Resolution>> {
+    if let Some(resolution) = target.resolve(ctx, |name| (name == "1").then_some(input))? {
+        return Ok(Some(resolution));
+    }
+    match matcher {
+        Some(matcher) => matcher.evaluate_match(input, ctx),
+        None => Ok(None),
+    }
+}
+
+impl Matcher for AllMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running all matcher");
+        let mut last = None;
+        for (idx, condition) in self.all.iter().enumerate() {
+            let segment = format!(".all[{idx}]");
+            match ctx.condition(|ctx| condition.evaluate_at(&segment, input, ctx))? {
+                Some(resolution) => last = Some(resolution),
+                None => {
+                    tracing::info!(idx, "all matcher did not match");
+                    ctx.reject(|| format!("`all[{idx}]` rejected the input"));
+                    return Ok(None);
+                }
+            }
+        }
+        if let Some(resolution) = resolve(self.target(), &self.matcher, input, ctx)? {
+            tracing::info!(%resolution, "all matcher resolved the request");
+            return Ok(Some(resolution));
+        }
+        // Without a target of its own, the last condition decides.
+        Ok(last)
+    }
+
+    fn compile(&self) -> Result<()> {
+        if self.all.is_empty() {
+            bail!("`all` needs at least one matcher");
+        }
+        self.target().check()?;
+        self.all.compile()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+impl Matcher for AnyMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(?input, "running any matcher");
+        for (idx, matcher) in self.any.iter().enumerate() {
+            if let Some(result) = matcher.evaluate_at(&format!(".any[{idx}]"), input, ctx)? {
+                tracing::info!("any matcher got match");
+                return Ok(Some(result));
+            }
+        }
+        tracing::info!("any matcher no match");
+        Ok(None)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.any.compile()
+    }
+}
+
+impl Matcher for NotMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running not matcher");
+        if ctx
+            .condition(|ctx| self.not.evaluate_at(".not", input, ctx))?
+            .is_some()
+        {
+            tracing::info!("not matcher did not match");
+            ctx.reject(|| "`not` accepted the input".to_string());
+            return Ok(None);
+        }
+        let result = resolve(self.target(), &self.matcher, input, ctx)?;
+        tracing::info!(?result, "not matcher matched");
+        Ok(result)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        self.not.compile()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::matching::{Matcher, Outcome, Request, explain};
+
+    const JIRA: &str = "match:
+- all:
+  - prefix: jira/
+  - not:
+      regex: '^jira/\\d+$'
+  - prefix: jira/
+    url: https://jira.example/browse/$2
+- any:
+  - exact: tickets
+    url: https://jira.example
+  - exact: issues
+    url: https://jira.example/issues
+";
+
+    #[test]
+    fn all_and_not() {
+        let cfg = Config::parse(JIRA).unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("jira/ABC-1").as_deref(),
+            Some("https://jira.example/browse/ABC-1")
+        );
+        assert_eq!(apply("jira/123"), None);
+        assert_eq!(apply("wiki/ABC-1"), None);
+        assert_eq!(
+            apply("issues").as_deref(),
+            Some("https://jira.example/issues")
+        );
+
+        let (_, trace) = explain(&cfg.matcher, Request::parse("jira/123"));
+        let all = &trace.unwrap().steps[0];
+        assert_eq!(all.path, "match[0]");
+        assert_eq!(
+            all.outcome,
+            Outcome::NoMatch {
+                reason: "`all[1]` rejected the input".to_string()
+            }
+        );
+        assert_eq!(all.steps[1].path, "match[0].all[1]");
+        assert_eq!(all.steps[1].steps[0].path, "match[0].all[1].not");
+    }
+
+    #[test]
+    fn not_forwards_its_input() {
+        let cfg = Config::parse(
+            "match:
+  not:
+    prefix: _
+  match:
+    regex: '^[a-z]+$'
+    url: https://example.com/$0
+",
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.matcher.apply("bear").unwrap().as_deref(),
+            Some("https://example.com/bear")
+        );
+        assert_eq!(cfg.matcher.apply("_bear").unwrap(), None);
+    }
+
+    #[test]
+    fn only_conditions_may_omit_the_target() {
+        let err = Config::parse("match:\n  all:\n  - prefix: jira/\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(
+                "invalid `all` matcher: it needs one of `url`, `text` or `match`, or a last matcher that has one"
+            ),
+            "{err}"
+        );
+        let err = Config::parse("match:\n  not:\n    prefix: jira/\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("invalid `not` matcher: it needs one of `url`, `text` or `match`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn errors_in_conditions_keep_their_location() {
+        let err = Config::parse("match:\n  all:\n  - prefix: jira/\n  - regex: '('\n  url: u\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid `regex` matcher"), "{err}");
+        assert!(err.ends_with("at line 4 column 5"), "{err}");
+        let err = Config::parse("match:\n  not:\n    prefx: _\n  url: u\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.ends_with("at line 3 column 5"), "{err}");
+    }
+
+    #[test]
+    fn match_of_a_condition_resolves_the_request() {
+        const INPUT: &str = "match:
+  all:
+  - prefix: a
+  - prefix: a
+    match:
+      any:
+      - prefix: /x
+        url: https://x.example
+      - exact: /b
+        url: https://b.example
+";
+        let cfg = Config::parse(INPUT).unwrap();
+        let (result, _) = explain(&cfg.matcher, Request::parse("a/b"));
+        assert_eq!(result.unwrap().unwrap().value(), "https://b.example");
+        assert_eq!(cfg.matcher.apply("a/c").unwrap(), None);
+
+        let err = Config::parse(&INPUT.replace("        url: https://x.example\n", ""))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(
+                "match.all[1].match.any[0]: invalid `prefix` matcher: it needs one of `url`, `text` or `match`"
+            ),
+            "{err}"
+        );
+    }
+}
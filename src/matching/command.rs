@@ -0,0 +1,366 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::{Result, bail};
+use tracing::instrument;
+
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::CommandMatcherConfig;
+
+/// Splits `input` on whitespace into words. Quotes (`"` or `'`) keep words
+/// together, and a backslash escapes the next character, except within
+/// single quotes.
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token: Option<String> = None;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => tokens.extend(token.take()),
+            '"' | '\'' => {
+                let token = token.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') if c == '"' => token.extend(chars.next()),
+                        Some(c) => token.push(c),
+                        None => return Err(format!("missing closing `{c}`")),
+                    }
+                }
+            }
+            '\\' => token
+                .get_or_insert_with(String::new)
+                .push(chars.next().unwrap_or('\\')),
+            c => token.get_or_insert_with(String::new).push(c),
+        }
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+/// Why the words after a command can't be bound to its arguments and flags.
+enum Unbound {
+    /// A required argument or flag is missing.
+    Missing(String),
+    /// The words don't fit the command, e.g. an unknown flag.
+    Invalid(String),
+}
+
+impl CommandMatcherConfig {
+    /// Binds the words after the command to the names of the arguments and
+    /// flags. Returns what's wrong with them if they don't fit.
+    fn bind(&self, words: &[String]) -> Result<HashMap<&str, String>, Unbound> {
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            if word == "--" {
+                positional.extend(words.by_ref());
+                break;
+            }
+            let Some(flag) = word.strip_prefix("--").filter(|flag| !flag.is_empty()) else {
+                positional.push(word);
+                continue;
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None => {
+                    let value = words.next().ok_or_else(|| {
+                        Unbound::Invalid(format!("flag `--{flag}` needs a value"))
+                    })?;
+                    (flag, value.clone())
+                }
+            };
+            let Some((name, _)) = self.flags.get_key_value(name) else {
+                return Err(Unbound::Invalid(format!("unknown flag `--{name}`")));
+            };
+            if flags.insert(name.as_str(), value).is_some() {
+                return Err(Unbound::Invalid(format!(
+                    "flag `--{name}` is given more than once"
+                )));
+            }
+        }
+        if let Some(extra) = positional.get(self.args.len()) {
+            return Err(Unbound::Invalid(format!("unexpected argument `{extra}`")));
+        }
+
+        let mut values = HashMap::new();
+        for (idx, arg) in self.args.iter().enumerate() {
+            let value = match (positional.get(idx), &arg.default) {
+                (Some(value), _) => value.to_string(),
+                (None, Some(default)) => default.clone(),
+                (None, None) if arg.required => {
+                    return Err(Unbound::Missing(format!("missing argument `{}`", arg.name)));
+                }
+                (None, None) => String::new(),
+            };
+            values.insert(arg.name.as_str(), value);
+        }
+        for (name, flag) in &self.flags {
+            let value = match (flags.remove(name.as_str()), &flag.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => default.clone(),
+                (None, None) if flag.required => {
+                    return Err(Unbound::Missing(format!("missing flag `--{name}`")));
+                }
+                (None, None) => String::new(),
+            };
+            values.insert(name.as_str(), value);
+        }
+        Ok(values)
+    }
+
+    /// A usage message like `usage: pr <number> [--repo <repo>]`, followed by
+    /// the descriptions of the arguments and flags.
+    fn usage(&self) -> String {
+        let mut usage = format!("usage: {}", self.command);
+        let mut descriptions = String::new();
+        for arg in &self.args {
+            let placeholder = format!("<{}>", arg.name);
+            if arg.required {
+                usage.push_str(&format!(" {placeholder}"));
+            } else {
+                usage.push_str(&format!(" [{placeholder}]"));
+            }
+            if let Some(description) = &arg.description {
+                descriptions.push_str(&format!("\n  {placeholder}  {description}"));
+            }
+        }
+        for (name, flag) in &self.flags {
+            if flag.required {
+                usage.push_str(&format!(" --{name} <{name}>"));
+            } else {
+                usage.push_str(&format!(" [--{name} <{name}>]"));
+            }
+            if let Some(description) = &flag.description {
+                descriptions.push_str(&format!("\n  --{name}  {description}"));
+            }
+        }
+        usage + &descriptions
+    }
+
+    fn is_command(&self, word: &str) -> bool {
+        if self.case_sensitive {
+            word == self.command
+        } else {
+            word.eq_ignore_ascii_case(&self.command)
+        }
+    }
+}
+
+impl Matcher for CommandMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running command matcher");
+        let input = input.trim();
+        let (word, rest) = match input.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim_start()),
+            None => (input, ""),
+        };
+        if !self.is_command(word) {
+            tracing::info!("command matcher did not match");
+            ctx.reject(|| format!("`{word}` is not the command `{}`", self.command));
+            return Ok(None);
+        }
+
+        let bound = tokenize(rest)
+            .map_err(Unbound::Invalid)
+            .and_then(|words| self.bind(&words));
+        let values = match bound {
+            Ok(values) => values,
+            // Someone who typed just the command wants to know what's missing,
+            // but a condition only checks the input, there is no one to tell.
+            Err(Unbound::Missing(problem)) if !ctx.in_condition() => {
+                tracing::info!(problem, "command matcher responding with usage");
+                return Ok(Some(Resolution::Text {
+                    text: format!("{problem}\n{}", self.usage()),
+                }));
+            }
+            // Input that doesn't fit is probably meant for another matcher.
+            Err(Unbound::Missing(problem) | Unbound::Invalid(problem)) => {
+                tracing::info!(problem, "command matcher did not match");
+                ctx.reject(|| problem);
+                return Ok(None);
+            }
+        };
+        if let Some(resolution) = self
+            .target()
+            .resolve(ctx, |name| values.get(name).map(String::as_str))?
+        {
+            tracing::info!(%resolution, "command matcher resolved the request");
+            return Ok(Some(resolution));
+        }
+        match &self.matcher {
+            Some(matcher) => {
+                tracing::info!("command matcher delegating to sub matcher");
+                matcher.evaluate_match(rest, ctx)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn compile(&self) -> Result<()> {
+        if self.command.is_empty() || self.command.contains(char::is_whitespace) {
+            bail!("the command has to be a single word");
+        }
+        let mut names = HashSet::new();
+        let arg_names = self.args.iter().map(|arg| arg.name.as_str());
+        for name in arg_names.chain(self.flags.keys().map(String::as_str)) {
+            let valid = name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+            if name.is_empty() || !valid {
+                bail!("`{name}` is not a valid name, use letters, digits, `_` and `-`");
+            }
+            if !names.insert(name) {
+                bail!("`{name}` is the name of more than one argument or flag");
+            }
+        }
+        let mut optional = None;
+        for arg in &self.args {
+            if arg.required && arg.default.is_some() {
+                bail!(
+                    "argument `{}` has a default, so it can't be required",
+                    arg.name
+                );
+            }
+            match optional {
+                Some(optional) if arg.required => bail!(
+                    "required argument `{}` comes after the optional argument `{optional}`",
+                    arg.name
+                ),
+                None if !arg.required => optional = Some(&arg.name),
+                _ => {}
+            }
+        }
+        for (name, flag) in &self.flags {
+            if flag.required && flag.default.is_some() {
+                bail!("flag `--{name}` has a default, so it can't be required");
+            }
+        }
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tokenize;
+    use crate::config::Config;
+    use crate::matching::Matcher;
+
+    const PR: &str = "match:
+  command: pr
+  args:
+  - name: number
+    required: true
+    description: Number of the pull request
+  flags:
+    repo:
+      default: infra
+  url: https://github.com/org/${repo}/pull/${number}
+";
+
+    #[test]
+    fn splits_words_and_respects_quotes() {
+        assert_eq!(
+            tokenize(r#"a  "b c" 'd\e' f\ g "" h"i""#).unwrap(),
+            ["a", "b c", r"d\e", "f g", "", "hi"]
+        );
+        assert_eq!(tokenize("").unwrap(), Vec::<String>::new());
+        assert_eq!(tokenize("'a").unwrap_err(), "missing closing `'`");
+    }
+
+    #[test]
+    fn binds_arguments_and_flags() {
+        let cfg = Config::parse(PR).unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("pr 1234").as_deref(),
+            Some("https://github.com/org/infra/pull/1234")
+        );
+        assert_eq!(
+            apply("PR 1234 --repo web").as_deref(),
+            Some("https://github.com/org/web/pull/1234")
+        );
+        assert_eq!(
+            apply("pr --repo=web 1234").as_deref(),
+            Some("https://github.com/org/web/pull/1234")
+        );
+        assert_eq!(apply("prs 1234"), None);
+    }
+
+    #[test]
+    fn responds_with_usage() {
+        let cfg = Config::parse(PR).unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        let usage = "usage: pr <number> [--repo <repo>]\n  <number>  Number of the pull request";
+        assert_eq!(
+            apply("pr").unwrap(),
+            format!("missing argument `number`\n{usage}")
+        );
+        assert_eq!(apply("pr 1 2"), None);
+        assert_eq!(apply("pr 1 --branch main"), None);
+    }
+
+    #[test]
+    fn rejects_input_that_does_not_fit() {
+        let input = "match:
+- command: pr
+  args:
+  - name: number
+    required: true
+  url: https://github.com/org/infra/pull/${number}
+- prefix: pr
+  text: not a pull request
+";
+        let cfg = Config::parse(input).unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        for input in ["pr 1 2", "pr 1 --repo web", "pr 1 --repo", "pr 1 '2"] {
+            assert_eq!(
+                apply(input).as_deref(),
+                Some("not a pull request"),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            apply("pr").as_deref(),
+            Some("missing argument `number`\nusage: pr <number>")
+        );
+    }
+
+    #[test]
+    fn vars_are_expanded() {
+        let input = format!(
+            "vars:\n  cmd: pr\n  repo: web\n{}",
+            PR.replace("command: pr", "command: ${var.cmd}")
+                .replace("default: infra", "default: ${var.repo}")
+        );
+        let cfg = Config::parse(&input).unwrap();
+        assert_eq!(
+            cfg.matcher.apply("pr 1").unwrap().as_deref(),
+            Some("https://github.com/org/web/pull/1")
+        );
+        let err = Config::parse(&input.replace("repo: web", "other: web"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("undefined variable `repo`"), "{err}");
+    }
+
+    #[test]
+    fn invalid_declarations_are_reported() {
+        let input = PR.replace("default: infra", "default: infra\n      required: true");
+        let err = Config::parse(&input).unwrap_err().to_string();
+        assert!(
+            err.contains(
+                "invalid `command` matcher: flag `--repo` has a default, so it can't be required"
+            ),
+            "{err}"
+        );
+        let input = PR.replace("repo:", "number:");
+        let err = Config::parse(&input).unwrap_err().to_string();
+        assert!(
+            err.contains("`number` is the name of more than one argument or flag"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use eyre::Result;
+use tracing::instrument;
+
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::ContainsMatcherConfig;
+
+impl ContainsMatcherConfig {
+    /// The byte offset of the first occurrence of `contains` in `input`.
+    fn find(&self, input: &str) -> Option<usize> {
+        if self.case_sensitive {
+            return input.find(&self.contains);
+        }
+        // ASCII lowercasing keeps the offsets intact.
+        input
+            .to_ascii_lowercase()
+            .find(&self.contains.to_ascii_lowercase())
+    }
+}
+
+impl Matcher for ContainsMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running contains matcher");
+
+        if let Some(start) = self.find(input) {
+            let end = start + self.contains.len();
+            let (before, matched, after) = (&input[..start], &input[start..end], &input[end..]);
+            if let Some(resolution) = self.target().resolve(ctx, |name| match name {
+                "1" => Some(before),
+                "2" => Some(matched),
+                "3" => Some(after),
+                _ => None,
+            })? {
+                tracing::info!(%resolution, "contains matcher resolved the request");
+                return Ok(Some(resolution));
+            }
+
+            if let Some(matcher) = &self.matcher {
+                tracing::info!("contains matcher delegating to sub matcher");
+                return matcher.evaluate_match(after, ctx);
+            }
+        }
+
+        tracing::info!("contains matcher did not match");
+        ctx.reject(|| format!("input does not contain `{}`", self.contains));
+        Ok(None)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::matching::Matcher;
+
+    #[test]
+    fn splits_at_the_first_occurrence() {
+        let cfg = Config::parse(
+            "match:
+- contains: ..
+  url: https://github.com/owner/repo/compare/$1...$3
+- contains: ' In '
+  match:
+    exact: wikipedia
+    url: https://en.wikipedia.org
+",
+        )
+        .unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("v1.0..main").as_deref(),
+            Some("https://github.com/owner/repo/compare/v1.0...main")
+        );
+        assert_eq!(
+            apply("v1..v2..v3").as_deref(),
+            Some("https://github.com/owner/repo/compare/v1...v2..v3")
+        );
+        assert_eq!(
+            apply("rust in Wikipedia").as_deref(),
+            Some("https://en.wikipedia.org")
+        );
+        assert_eq!(apply("v1.0"), None);
+    }
+}
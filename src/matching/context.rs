@@ -0,0 +1,374 @@
+use std::borrow::Cow;
+
+use eyre::Result;
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+
+use super::Resolution;
+use crate::config::InputSource;
+
+/// The request a matcher tree is evaluated for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Request {
+    /// The decoded path, without the leading `/`.
+    pub path: String,
+    /// The query string as it was sent, without the leading `?`.
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Request {
+    /// Splits a request target like `search?q=rust#top` into its parts.
+    pub fn parse(target: &str) -> Self {
+        let (target, fragment) = match target.split_once('#') {
+            Some((target, fragment)) => (target, Some(fragment.to_string())),
+            None => (target, None),
+        };
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        Request {
+            path: path.to_string(),
+            query,
+            fragment,
+        }
+    }
+
+    /// Reads an input typed on the command line (or in a config's `tests`).
+    ///
+    /// Only a request target that starts with `/` or a full `http(s)` URL is
+    /// split into path, query and fragment, with the path decoded like the
+    /// daemon does. Anything else is the path as typed, so `c# tutorial` or
+    /// `what is rust?` reach the matchers in one piece.
+    pub fn from_input(input: &str) -> Self {
+        if let Some(target) = input.strip_prefix('/') {
+            return Request::parse(target).decoded();
+        }
+        match url::Url::parse(input) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Request {
+                path: url.path().trim_start_matches('/').to_string(),
+                query: url.query().map(str::to_string),
+                fragment: url.fragment().map(str::to_string),
+            }
+            .decoded(),
+            _ => Request {
+                path: input.to_string(),
+                query: None,
+                fragment: None,
+            },
+        }
+    }
+
+    /// Percent-decodes the path.
+    fn decoded(mut self) -> Self {
+        self.path = percent_decode_str(&self.path)
+            .decode_utf8_lossy()
+            .into_owned();
+        self
+    }
+
+    /// The path followed by the query, if there is one.
+    pub fn target(&self) -> String {
+        match &self.query {
+            Some(query) => format!("{}?{query}", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// State that is passed along while evaluating the matcher tree for a single
+/// request.
+#[derive(Debug, Default)]
+pub struct Context {
+    request: Request,
+    /// Decoded query parameters, in the order they were sent.
+    params: Vec<(String, String)>,
+    /// Whether the running matcher is a condition of an `all` or `not`
+    /// matcher, which only needs to accept its input.
+    condition: bool,
+    trace: Option<Tracer>,
+}
+
+/// One matcher that was tried while evaluating an input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Step {
+    /// Where the matcher is in the config, e.g. `match[3].match[0]`.
+    pub path: String,
+    /// Short description of the matcher, e.g. ``prefix `animals/` ``.
+    pub matcher: String,
+    /// The input the matcher saw.
+    pub input: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "kebab-case")]
+pub enum Outcome {
+    /// The matcher resolved the request with its own `url` or `text`.
+    Matched {
+        #[serde(flatten)]
+        resolution: Resolution,
+    },
+    /// A sub matcher resolved the request.
+    Delegated {
+        #[serde(flatten)]
+        resolution: Resolution,
+    },
+    NoMatch {
+        reason: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Tracer {
+    /// Steps that are still being evaluated, innermost last.
+    stack: Vec<(Step, Option<String>)>,
+    root: Option<Step>,
+}
+
+impl Context {
+    pub fn new(request: Request) -> Self {
+        let params = request
+            .query
+            .as_deref()
+            .map(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        Context {
+            request,
+            params,
+            condition: false,
+            trace: None,
+        }
+    }
+
+    /// Records every step of the evaluation, see [`Context::into_trace`].
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Tracer::default());
+        self
+    }
+
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// The decoded value of the first query parameter called `name`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Placeholders that are available to every matcher: `${path}`,
+    /// `${query}`, `${fragment}` and `${query.NAME}`.
+    pub(crate) fn placeholder(&self, name: &str) -> Option<&str> {
+        match name {
+            "path" => Some(&self.request.path),
+            "query" => self.request.query.as_deref(),
+            "fragment" => self.request.fragment.as_deref(),
+            _ => self.param(name.strip_prefix("query.")?),
+        }
+    }
+
+    /// The input for a matcher with the given `input` option, where
+    /// `forwarded` is what its parent passed on. `None` if the request
+    /// doesn't have the requested part.
+    pub(crate) fn select_input<'a>(
+        &self,
+        source: Option<&InputSource>,
+        forwarded: &'a str,
+    ) -> Option<Cow<'a, str>> {
+        let Some(source) = source else {
+            return Some(Cow::Borrowed(forwarded));
+        };
+        let selected = match source {
+            InputSource::Path => Some(self.request.path.clone()),
+            InputSource::Query => self.request.query.clone(),
+            InputSource::Target => Some(self.request.target()),
+            InputSource::Fragment => self.request.fragment.clone(),
+            InputSource::Param(name) => self.param(name).map(str::to_string),
+        };
+        selected.map(Cow::Owned)
+    }
+
+    /// Whether the running matcher is a condition, see [`Context::condition`].
+    pub(crate) fn in_condition(&self) -> bool {
+        self.condition
+    }
+
+    /// Runs `f` with the matchers it evaluates treated as conditions: a
+    /// matcher without `url`, `text` or `match` resolves to
+    /// [`Resolution::Accepted`] when it accepts its input.
+    pub(crate) fn condition<T>(&mut self, f: impl FnOnce(&mut Context) -> T) -> T {
+        self.with_condition(true, f)
+    }
+
+    /// Runs `f` with the matchers it evaluates resolving the request again,
+    /// as in the `match` of a condition.
+    pub(crate) fn resolving<T>(&mut self, f: impl FnOnce(&mut Context) -> T) -> T {
+        self.with_condition(false, f)
+    }
+
+    fn with_condition<T>(&mut self, condition: bool, f: impl FnOnce(&mut Context) -> T) -> T {
+        let outer = std::mem::replace(&mut self.condition, condition);
+        let result = f(self);
+        self.condition = outer;
+        result
+    }
+
+    /// The recorded evaluation, if tracing was enabled and a matcher ran.
+    pub fn into_trace(self) -> Option<Step> {
+        self.trace.and_then(|tracer| tracer.root)
+    }
+
+    /// Starts a step for a matcher at `segment` below the current step.
+    pub(crate) fn enter(&mut self, segment: &str, matcher: impl FnOnce() -> String, input: &str) {
+        let Some(tracer) = &mut self.trace else {
+            return;
+        };
+        let path = match tracer.stack.last() {
+            Some((parent, _)) if segment.starts_with(['.', '[']) => {
+                format!("{}{segment}", parent.path)
+            }
+            _ => segment.to_string(),
+        };
+        let step = Step {
+            path,
+            matcher: matcher(),
+            input: input.to_string(),
+            outcome: Outcome::NoMatch {
+                reason: String::new(),
+            },
+            steps: Vec::new(),
+        };
+        tracer.stack.push((step, None));
+    }
+
+    /// Records why the current matcher rejects its input.
+    pub(crate) fn reject(&mut self, reason: impl FnOnce() -> String) {
+        if let Some((_, rejection)) = self.trace.as_mut().and_then(|t| t.stack.last_mut()) {
+            *rejection = Some(reason());
+        }
+    }
+
+    /// Finishes the current step with the matcher's result.
+    pub(crate) fn leave(&mut self, result: &Result<Option<Resolution>>) {
+        let Some(tracer) = &mut self.trace else {
+            return;
+        };
+        let Some((mut step, rejection)) = tracer.stack.pop() else {
+            return;
+        };
+        let delegated = step.steps.iter().any(|s| {
+            matches!(
+                s.outcome,
+                Outcome::Matched { .. } | Outcome::Delegated { .. }
+            )
+        });
+        step.outcome = match result {
+            Ok(Some(resolution)) if delegated => Outcome::Delegated {
+                resolution: resolution.clone(),
+            },
+            Ok(Some(resolution)) => Outcome::Matched {
+                resolution: resolution.clone(),
+            },
+            Ok(None) => Outcome::NoMatch {
+                reason: rejection.unwrap_or_else(|| "no sub matcher matched".to_string()),
+            },
+            Err(err) => Outcome::Error {
+                message: format!("{err:#}"),
+            },
+        };
+        match tracer.stack.last_mut() {
+            Some((parent, _)) => parent.steps.push(step),
+            None => tracer.root = Some(step),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::matching::explain;
+
+    #[test]
+    fn parse_request_target() {
+        let request = Request::parse("search?q=a+b&q=c#top");
+        assert_eq!(
+            request,
+            Request {
+                path: "search".into(),
+                query: Some("q=a+b&q=c".into()),
+                fragment: Some("top".into()),
+            }
+        );
+        let ctx = Context::new(request);
+        assert_eq!(ctx.param("q"), Some("a b"));
+        assert_eq!(ctx.placeholder("query.q"), Some("a b"));
+        assert_eq!(ctx.placeholder("query.missing"), None);
+    }
+
+    #[test]
+    fn query_placeholder_is_not_encoded_again() {
+        let cfg = Config::parse(
+            "match:\n  input: path\n  exact: search\n  url: https://example.com/search?${query}&page=${query.p}\n",
+        )
+        .unwrap();
+        let (result, _) = explain(&cfg.matcher, Request::parse("search?q=rust&x=1&p=a%26b"));
+        assert_eq!(
+            result.unwrap().unwrap().value(),
+            "https://example.com/search?q=rust&x=1&p=a%26b&page=a%26b"
+        );
+    }
+
+    #[test]
+    fn input_is_a_path_unless_it_is_a_url() {
+        let raw = |path: &str| Request {
+            path: path.into(),
+            query: None,
+            fragment: None,
+        };
+        assert_eq!(Request::from_input("c# tutorial"), raw("c# tutorial"));
+        assert_eq!(Request::from_input("what is rust?"), raw("what is rust?"));
+        let expected = Request {
+            path: "s/c# tutorial".into(),
+            query: Some("lang=en".into()),
+            fragment: Some("top".into()),
+        };
+        assert_eq!(
+            Request::from_input("/s/c%23%20tutorial?lang=en#top"),
+            expected
+        );
+        assert_eq!(
+            Request::from_input("http://localhost:8081/s/c%23 tutorial?lang=en#top"),
+            expected
+        );
+    }
+
+    #[test]
+    fn select_input() {
+        let ctx = Context::new(Request::parse("search?q=rust"));
+        let select = |source: InputSource| {
+            ctx.select_input(Some(&source), "forwarded")
+                .map(|input| input.into_owned())
+        };
+        assert_eq!(ctx.select_input(None, "forwarded").unwrap(), "forwarded");
+        assert_eq!(select(InputSource::Path).unwrap(), "search");
+        assert_eq!(select(InputSource::Query).unwrap(), "q=rust");
+        assert_eq!(select(InputSource::Target).unwrap(), "search?q=rust");
+        assert_eq!(select(InputSource::Fragment), None);
+        assert_eq!(select(InputSource::Param("q".into())).unwrap(), "rust");
+    }
+}
@@ -1,12 +1,12 @@
 use eyre::Result;
 use tracing::instrument;
 
-use super::Matcher;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
 use crate::config::ExactMatcherConfig;
 
 impl Matcher for ExactMatcherConfig {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
         tracing::info!(matcher = ?self, input, "running exact matcher");
         let mut candidate = input;
         if self.trim {
@@ -18,35 +18,39 @@ impl Matcher for ExactMatcherConfig {
             candidate.eq_ignore_ascii_case(&self.exact)
         };
         if matches {
-            if let Some(url) = &self.url {
-                let redirect = url.replace("$1", candidate);
-                tracing::info!(%redirect, "exact matcher produced redirect");
-                return Ok(Some(redirect));
+            if let Some(resolution) = self
+                .target()
+                .resolve(ctx, |name| (name == "1").then_some(candidate))?
+            {
+                tracing::info!(%resolution, "exact matcher resolved the request");
+                return Ok(Some(resolution));
             }
             if let Some(matcher) = &self.matcher {
                 tracing::info!("exact matcher delegating to sub matcher");
-                return matcher.apply(candidate);
+                return matcher.evaluate_match(candidate, ctx);
             }
         }
         tracing::info!("exact matcher did not match");
+        ctx.reject(|| format!("`{candidate}` is not `{}`", self.exact));
         Ok(None)
     }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use crate::config::Config;
+    use crate::matching::Matcher;
 
     #[test]
     fn match_redirect() {
-        let cfg = ExactMatcherConfig {
-            exact: "Hello".into(),
-            case_sensitive: false,
-            trim: true,
-            url: Some("https://example.com?q=$1".into()),
-            matcher: None,
-        };
-        let result = cfg.apply("Hello").unwrap();
+        let cfg =
+            Config::parse("match:\n  exact: Hello\n  url: https://example.com?q=$1\n").unwrap();
+        let result = cfg.matcher.apply("Hello").unwrap();
         assert_eq!(result.unwrap(), "https://example.com?q=Hello");
     }
 }
@@ -1,28 +1,41 @@
 use eyre::Result;
 use tracing::instrument;
 
-use super::Matcher;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
 use crate::config::FuzzyMatcherConfig;
 
 impl Matcher for FuzzyMatcherConfig {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
         tracing::info!(matcher = ?self, input, "running fuzzy matcher");
 
         let distance = strsim::levenshtein(input, &self.fuzzy);
         if distance as u32 <= self.tolerance {
-            if let Some(url) = &self.url {
-                let redirect = url.replace("$1", input);
-                tracing::info!(%redirect, "fuzzy matcher produced redirect");
-                return Ok(Some(redirect));
+            if let Some(resolution) = self
+                .target()
+                .resolve(ctx, |name| (name == "1").then_some(input))?
+            {
+                tracing::info!(%resolution, "fuzzy matcher resolved the request");
+                return Ok(Some(resolution));
             }
             if let Some(matcher) = &self.matcher {
                 tracing::info!("fuzzy matcher delegating to sub matcher");
-                return matcher.apply(input);
+                return matcher.evaluate_match(input, ctx);
             }
         }
 
         tracing::info!("fuzzy matcher did not match");
+        ctx.reject(|| {
+            format!(
+                "distance {distance} exceeds the tolerance of {}",
+                self.tolerance
+            )
+        });
         Ok(None)
     }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
 }
@@ -0,0 +1,192 @@
+use eyre::{Result, WrapErr, bail, eyre};
+use regex::{Regex, RegexBuilder};
+use tracing::instrument;
+
+use super::regex::capture;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::GlobMatcherConfig;
+
+impl GlobMatcherConfig {
+    /// The glob compiled to a regular expression, built on first use.
+    fn regex(&self) -> Result<&Regex> {
+        self.compiled.get_or_try_init(|| {
+            let pattern =
+                translate(&self.glob).wrap_err_with(|| format!("invalid glob `{}`", self.glob))?;
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .build()
+                .wrap_err_with(|| format!("invalid glob `{}`", self.glob))
+        })
+    }
+}
+
+/// Translates a glob into a regular expression for the whole input, with a
+/// capture group for every wildcard and every set of alternatives.
+fn translate(glob: &str) -> Result<String> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.as_str().starts_with("*/") => {
+                chars.nth(1);
+                // Also matches no directory at all, e.g. `a/**/b` matches `a/b`.
+                pattern.push_str("(?:(.*)/)?");
+            }
+            '*' if chars.as_str().starts_with('*') => {
+                chars.next();
+                pattern.push_str("(.*)");
+            }
+            '*' => pattern.push_str("([^/]*)"),
+            '?' => pattern.push_str("([^/])"),
+            '{' => {
+                let mut alternatives = vec![String::new()];
+                loop {
+                    let alternative = alternatives.last_mut().expect("starts with one");
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(',') => alternatives.push(String::new()),
+                        Some('\\') => alternative.push(escaped(chars.next())?),
+                        Some(c @ ('{' | '*' | '?')) => bail!("`{c}` is not allowed inside `{{}}`"),
+                        Some(c) => alternative.push(c),
+                        None => bail!("`{{` is never closed"),
+                    }
+                }
+                let alternatives: Vec<_> = alternatives.iter().map(|a| regex::escape(a)).collect();
+                pattern.push_str(&format!("({})", alternatives.join("|")));
+            }
+            '}' => bail!("`}}` without `{{`"),
+            '\\' => pattern.push_str(&regex::escape(&escaped(chars.next())?.to_string())),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Ok(pattern)
+}
+
+/// The character after a `\`.
+fn escaped(c: Option<char>) -> Result<char> {
+    c.ok_or_else(|| eyre!("`\\` at the end, use `\\\\` for a backslash"))
+}
+
+impl Matcher for GlobMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running glob matcher");
+
+        let regex = self.regex()?;
+        let Some(caps) = regex.captures(input) else {
+            tracing::info!("glob matcher did not match");
+            ctx.reject(|| "glob does not match the input".to_string());
+            return Ok(None);
+        };
+
+        if let Some(resolution) = self.target().resolve(ctx, capture(regex, &caps))? {
+            tracing::info!(%resolution, "glob matcher resolved the request");
+            return Ok(Some(resolution));
+        }
+
+        if let Some(matcher) = &self.matcher {
+            tracing::info!("glob matcher delegating to sub matcher");
+            return matcher.evaluate_match(input, ctx);
+        }
+
+        tracing::info!("glob matcher did not match");
+        Ok(None)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.regex()?;
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::translate;
+    use crate::config::Config;
+    use crate::matching::Matcher;
+
+    #[test]
+    fn translates_wildcards() {
+        assert_eq!(
+            translate("docs/**/*.{md,txt}").unwrap(),
+            r"^docs/(?:(.*)/)?([^/]*)\.(md|txt)$"
+        );
+        assert_eq!(translate("a/**").unwrap(), "^a/(.*)$");
+        assert_eq!(translate(r"a?\*").unwrap(), r"^a([^/])\*$");
+        assert!(translate("{a,b").is_err());
+        assert!(translate("{a,*}").is_err());
+        assert!(translate("a}").is_err());
+    }
+
+    #[test]
+    fn wildcards_are_placeholders() {
+        let cfg = Config::parse(
+            "match:
+- glob: gh/*/*/{issues,pull}/?*
+  url: https://github.com/$1/$2/$3/$4$5
+- glob: docs/**
+  url: https://docs.example/$1
+",
+        )
+        .unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("gh/rust-lang/rust/Pull/42").as_deref(),
+            Some("https://github.com/rust-lang/rust/Pull/42")
+        );
+        assert_eq!(apply("gh/rust-lang/rust/commits/42"), None);
+        assert_eq!(apply("gh/rust-lang/rust/issues/"), None);
+        assert_eq!(
+            apply("docs/guide/intro").as_deref(),
+            Some("https://docs.example/guide/intro")
+        );
+    }
+
+    #[test]
+    fn double_star_matches_no_directories() {
+        let cfg =
+            Config::parse("match:\n  glob: docs/**/*.md\n  url: https://docs.example/$2?dir=$1\n")
+                .unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("docs/a.md").as_deref(),
+            Some("https://docs.example/a?dir=")
+        );
+        assert_eq!(
+            apply("docs/guide/intro/a.md").as_deref(),
+            Some("https://docs.example/a?dir=guide%2Fintro")
+        );
+        assert_eq!(apply("docsa.md"), None);
+    }
+
+    #[test]
+    fn vars_are_expanded() {
+        let input =
+            "vars:\n  d: docs\nmatch:\n  glob: ${var.d}/*\n  url: https://docs.example/$1\n";
+        let cfg = Config::parse(input).unwrap();
+        assert_eq!(
+            cfg.matcher.apply("docs/a").unwrap().as_deref(),
+            Some("https://docs.example/a")
+        );
+        let err = Config::parse(&input.replace("${var.d}", "${var.e}"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("invalid `glob` matcher: undefined variable `e`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn invalid_glob_is_reported() {
+        let err = Config::parse("match:\n  glob: '{a,b'\n  url: https://example.com\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("invalid `glob` matcher: invalid glob `{a,b`: `{` is never closed"),
+            "{err}"
+        );
+    }
+}
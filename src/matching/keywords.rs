@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use eyre::{Result, bail, eyre};
+use tracing::instrument;
+
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::{KeywordConfig, KeywordsMatcherConfig};
+
+impl KeywordsMatcherConfig {
+    /// Splits the input into the keyword and its argument.
+    fn split<'i>(&self, input: &'i str) -> (&'i str, &'i str) {
+        let input = input.trim();
+        if !self.argument {
+            return (input, "");
+        }
+        match input.split_once(char::is_whitespace) {
+            Some((keyword, argument)) => (keyword, argument.trim_start()),
+            None => (input, ""),
+        }
+    }
+
+    fn normalize(&self, keyword: &str) -> String {
+        if self.case_sensitive {
+            keyword.to_string()
+        } else {
+            keyword.to_ascii_lowercase()
+        }
+    }
+
+    fn table(&self) -> Result<&HashMap<String, String>> {
+        self.compiled.get_or_try_init(|| {
+            let mut table = HashMap::new();
+            for (keyword, entry) in &self.keywords {
+                for word in std::iter::once(keyword).chain(&entry.aliases) {
+                    if let Some(other) = table.insert(self.normalize(word), keyword.clone()) {
+                        if other == *keyword {
+                            bail!("keyword `{keyword}` has the alias `{word}` twice");
+                        }
+                        bail!("`{word}` is a keyword of both `{other}` and `{keyword}`");
+                    }
+                }
+            }
+            Ok(table)
+        })
+    }
+
+    /// Resolves the request with the entry of `keyword`.
+    fn evaluate_entry(
+        &self,
+        keyword: &str,
+        entry: &KeywordConfig,
+        argument: &str,
+        forwarded: &str,
+        ctx: &mut Context,
+    ) -> Result<Option<Resolution>> {
+        if let Some(resolution) = entry.target().resolve(ctx, |name| match name {
+            "1" => Some(argument),
+            "keyword" => Some(keyword),
+            _ => None,
+        })? {
+            tracing::info!(%resolution, "keywords matcher resolved the request");
+            return Ok(Some(resolution));
+        }
+        match &entry.matcher {
+            Some(matcher) => {
+                tracing::info!("keywords matcher delegating to sub matcher");
+                matcher.evaluate_match(forwarded, ctx)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Matcher for KeywordsMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running keywords matcher");
+        let (word, argument) = self.split(input);
+        let Some(keyword) = self.table()?.get(&self.normalize(word)) else {
+            tracing::info!("keywords matcher did not match");
+            ctx.reject(|| format!("`{word}` is not a keyword"));
+            return Ok(None);
+        };
+        let entry = &self.keywords[keyword];
+        // The sub matcher of an entry works on the argument, like the one of a
+        // `prefix` matcher, or on the keyword, like the one of an `exact`.
+        let forwarded = if self.argument { argument } else { word };
+
+        ctx.enter(
+            &format!(".keywords.{keyword}"),
+            || format!("keyword `{keyword}`"),
+            forwarded,
+        );
+        let result = self.evaluate_entry(keyword, entry, argument, forwarded, ctx);
+        ctx.leave(&result);
+        result
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.table()?;
+        for (keyword, entry) in &self.keywords {
+            let context = |err: &dyn fmt::Display| eyre!("keyword `{keyword}`: {err:#}");
+            if entry.common.url.is_none() && entry.common.text.is_none() && entry.matcher.is_none()
+            {
+                return Err(context(&"it needs one of `url`, `text` or `match`"));
+            }
+            entry.target().check().map_err(|err| context(&err))?;
+            compile_sub_matcher(&entry.matcher)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::matching::{Matcher, Outcome, Request, explain};
+
+    const KEYWORDS: &str = "match:
+  keywords:
+    gh:
+      aliases: [github]
+      url: https://github.com/search?q=$1
+    mail:
+      url: https://mail.example.com
+    docs:
+      match:
+        exact: ''
+        url: https://docs.example.com
+  argument: true
+";
+
+    #[test]
+    fn first_word_is_the_keyword() {
+        let cfg = Config::parse(KEYWORDS).unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("gh rust-lang/rust").as_deref(),
+            Some("https://github.com/search?q=rust-lang%2Frust")
+        );
+        assert_eq!(
+            apply("GitHub  tokio").as_deref(),
+            Some("https://github.com/search?q=tokio")
+        );
+        assert_eq!(apply("mail").as_deref(), Some("https://mail.example.com"));
+        assert_eq!(apply("docs").as_deref(), Some("https://docs.example.com"));
+        assert_eq!(apply("docs serde"), None);
+        assert_eq!(apply("gitlab rust"), None);
+
+        let (_, trace) = explain(&cfg.matcher, Request::parse("docs"));
+        let trace = trace.unwrap();
+        assert!(matches!(trace.outcome, Outcome::Delegated { .. }));
+        assert_eq!(trace.steps[0].path, "match.keywords.docs");
+        assert_eq!(trace.steps[0].steps[0].path, "match.keywords.docs.match");
+    }
+
+    #[test]
+    fn whole_input_is_the_keyword() {
+        let input = KEYWORDS.replace("  argument: true\n", "");
+        let cfg = Config::parse(&input).unwrap();
+        assert_eq!(
+            cfg.matcher.apply(" Mail ").unwrap().as_deref(),
+            Some("https://mail.example.com")
+        );
+        assert_eq!(cfg.matcher.apply("mail me").unwrap(), None);
+    }
+
+    #[test]
+    fn keywords_must_be_unique() {
+        let input = KEYWORDS.replace("[github]", "[github, Mail]");
+        let err = Config::parse(&input).unwrap_err().to_string();
+        assert!(
+            err.contains("invalid `keywords` matcher: `mail` is a keyword of both `gh` and `mail`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn vars_are_expanded() {
+        let input = format!(
+            "vars:\n  hub: github\n  mail: mail.example.com\n{}",
+            KEYWORDS
+                .replace("[github]", "['${var.hub}']")
+                .replace("url: https://mail.example.com", "text: ${var.mail}")
+        );
+        let cfg = Config::parse(&input).unwrap();
+        assert_eq!(
+            cfg.matcher.apply("github tokio").unwrap().as_deref(),
+            Some("https://github.com/search?q=tokio")
+        );
+        assert_eq!(
+            cfg.matcher.apply("mail").unwrap().as_deref(),
+            Some("mail.example.com")
+        );
+
+        let err = Config::parse(&input.replace("${var.mail}", "${var.mial}"))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "match.keywords.mail: undefined variable `mial` at line 10 column 7"
+        );
+    }
+}
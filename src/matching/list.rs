@@ -1,15 +1,15 @@
 use eyre::Result;
 use tracing::instrument;
 
-use super::Matcher;
+use super::{Context, Matcher, Resolution};
 use crate::config::MatcherConfig;
 
 impl Matcher for Vec<MatcherConfig> {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
         tracing::info!(?input, "running list matcher");
-        for matcher in self {
-            if let Some(result) = matcher.apply(input)? {
+        for (idx, matcher) in self.iter().enumerate() {
+            if let Some(result) = matcher.evaluate_at(&format!("[{idx}]"), input, ctx)? {
                 tracing::info!("list matcher got match");
                 return Ok(Some(result));
             }
@@ -17,31 +17,30 @@ impl Matcher for Vec<MatcherConfig> {
         tracing::info!("list matcher no match");
         Ok(None)
     }
+
+    fn compile(&self) -> Result<()> {
+        self.iter().try_for_each(Matcher::compile)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::config::ExactMatcherConfig;
-    use crate::config::MatcherConfig;
+    use crate::config::{Config, MatcherConfig};
     use crate::matching::Matcher;
 
     #[test]
     fn picks_first_match() {
-        let m1 = MatcherConfig::Exact(ExactMatcherConfig {
-            exact: "One".into(),
-            case_sensitive: false,
-            trim: true,
-            url: Some("https://one.example".into()),
-            matcher: None,
-        });
-        let m2 = MatcherConfig::Exact(ExactMatcherConfig {
-            exact: "Two".into(),
-            case_sensitive: false,
-            trim: true,
-            url: Some("https://two.example".into()),
-            matcher: None,
-        });
-        let list = vec![m1, m2];
+        let input = "match:
+- exact: One
+  url: https://one.example
+- exact: Two
+  url: https://two.example
+- prefix: T
+  url: https://t.example
+";
+        let MatcherConfig::List(list) = Config::parse(input).unwrap().matcher else {
+            panic!("expected a list");
+        };
         let result = list.apply("Two").unwrap();
         assert_eq!(result.unwrap(), "https://two.example");
     }
@@ -4,35 +4,248 @@ pub trait Matcher {
     /// Applies this matcher to the provided `input` URL.
     ///
     /// Returns `Ok(Some(url))` if the matcher accepts the input and wants to
-    /// redirect to `url`. Returns `Ok(None)` if the matcher does not match.
-    fn apply(&self, input: &str) -> Result<Option<String>>;
+    /// redirect to `url` (or respond with a text). Returns `Ok(None)` if the
+    /// matcher does not match.
+    fn apply(&self, input: &str) -> Result<Option<String>> {
+        let resolution = self.evaluate(input, &mut Context::default())?;
+        Ok(resolution.map(|resolution| resolution.value().to_string()))
+    }
+
+    /// Like [`Matcher::apply`], but with a [`Context`] that is passed on to sub
+    /// matchers, and the full [`Resolution`].
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>>;
+
+    /// Prepares everything that doesn't depend on the input (e.g. regular
+    /// expressions) so that `apply` doesn't have to.
+    ///
+    /// Called once when the configuration is loaded, which also surfaces
+    /// configuration errors before the first request comes in.
+    fn compile(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
+mod combinators;
+mod command;
+mod contains;
+mod context;
 mod exact;
 mod fuzzy;
+mod glob;
+mod keywords;
 mod list;
 mod prefix;
+mod query;
+mod reference;
 mod regex;
+mod resolution;
+mod suffix;
+mod template;
 
-use crate::config::MatcherConfig;
+pub use context::{Context, Outcome, Request, Step};
+pub(crate) use reference::link;
+pub use resolution::Resolution;
+pub use template::Template;
+
+use crate::config::{InputSource, MatcherConfig};
 use tracing::instrument;
 
 impl Matcher for MatcherConfig {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        match self {
+            MatcherConfig::Exact(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Prefix(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Suffix(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Contains(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Fuzzy(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Glob(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Regex(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Query(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Ref(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Keywords(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Command(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::All(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Any(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::Not(cfg) => cfg.evaluate(input, ctx),
+            MatcherConfig::List(list) => list.evaluate(input, ctx),
+        }
+    }
+
+    fn compile(&self) -> Result<()> {
         match self {
-            MatcherConfig::Exact(cfg) => cfg.apply(input),
-            MatcherConfig::Prefix(cfg) => cfg.apply(input),
-            MatcherConfig::Fuzzy(cfg) => cfg.apply(input),
-            MatcherConfig::Regex(cfg) => cfg.apply(input),
-            MatcherConfig::List(list) => list.apply(input),
+            MatcherConfig::Exact(cfg) => cfg.compile(),
+            MatcherConfig::Prefix(cfg) => cfg.compile(),
+            MatcherConfig::Suffix(cfg) => cfg.compile(),
+            MatcherConfig::Contains(cfg) => cfg.compile(),
+            MatcherConfig::Fuzzy(cfg) => cfg.compile(),
+            MatcherConfig::Glob(cfg) => cfg.compile(),
+            MatcherConfig::Regex(cfg) => cfg.compile(),
+            MatcherConfig::Query(cfg) => cfg.compile(),
+            MatcherConfig::Ref(cfg) => cfg.compile(),
+            MatcherConfig::Keywords(cfg) => cfg.compile(),
+            MatcherConfig::Command(cfg) => cfg.compile(),
+            MatcherConfig::All(cfg) => cfg.compile(),
+            MatcherConfig::Any(cfg) => cfg.compile(),
+            MatcherConfig::Not(cfg) => cfg.compile(),
+            MatcherConfig::List(list) => list.compile(),
         }
     }
 }
 
 impl Matcher for Box<MatcherConfig> {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
-        self.as_ref().apply(input)
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        self.as_ref().evaluate(input, ctx)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.as_ref().compile()
+    }
+}
+
+impl MatcherConfig {
+    /// Evaluates this matcher as the node `segment` (e.g. `.match` or `[2]`)
+    /// below the matcher that is currently running, recording a step in the
+    /// context's trace. A segment that doesn't start with `.` or `[` is a
+    /// path of its own, like `definitions.jira`.
+    ///
+    /// This is also where the matcher's `input` option takes effect.
+    pub fn evaluate_at(
+        &self,
+        segment: &str,
+        input: &str,
+        ctx: &mut Context,
+    ) -> Result<Option<Resolution>> {
+        let source = self.input_source();
+        let Some(input) = ctx.select_input(source, input) else {
+            ctx.enter(segment, || self.describe(), "");
+            ctx.reject(|| format!("the request has no {}", describe_source(source)));
+            let result = Ok(None);
+            ctx.leave(&result);
+            return result;
+        };
+        ctx.enter(segment, || self.describe(), &input);
+        let result = self.evaluate(&input, ctx);
+        ctx.leave(&result);
+        result
+    }
+
+    /// Evaluates this matcher as the `match` of another one, which resolves
+    /// the request even if the other one is a condition.
+    pub(crate) fn evaluate_match(
+        &self,
+        input: &str,
+        ctx: &mut Context,
+    ) -> Result<Option<Resolution>> {
+        ctx.resolving(|ctx| self.evaluate_at(".match", input, ctx))
+    }
+
+    /// The `input` option of this matcher.
+    fn input_source(&self) -> Option<&InputSource> {
+        match self {
+            MatcherConfig::Exact(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Prefix(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Suffix(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Contains(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Fuzzy(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Glob(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Regex(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Keywords(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Command(cfg) => cfg.input.as_ref(),
+            MatcherConfig::All(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Any(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Not(cfg) => cfg.input.as_ref(),
+            MatcherConfig::Query(_) | MatcherConfig::Ref(_) | MatcherConfig::List(_) => None,
+        }
+    }
+
+    /// A short, human readable description of this matcher.
+    pub fn describe(&self) -> String {
+        match self {
+            MatcherConfig::Exact(cfg) => format!("exact `{}`", cfg.exact),
+            MatcherConfig::Prefix(cfg) => format!("prefix `{}`", cfg.prefix),
+            MatcherConfig::Suffix(cfg) => format!("suffix `{}`", cfg.suffix),
+            MatcherConfig::Contains(cfg) => format!("contains `{}`", cfg.contains),
+            MatcherConfig::Fuzzy(cfg) => {
+                format!("fuzzy `{}` (tolerance {})", cfg.fuzzy, cfg.tolerance)
+            }
+            MatcherConfig::Glob(cfg) => format!("glob `{}`", cfg.glob),
+            MatcherConfig::Regex(cfg) => format!("regex `{}`", cfg.regex),
+            MatcherConfig::Query(cfg) => format!("query parameter `{}`", cfg.query),
+            MatcherConfig::Ref(cfg) => format!("ref `{}`", cfg.name),
+            MatcherConfig::Keywords(cfg) => format!("{} keywords", cfg.keywords.len()),
+            MatcherConfig::Command(cfg) => format!("command `{}`", cfg.command),
+            MatcherConfig::All(cfg) => format!("all of {} matchers", cfg.all.len()),
+            MatcherConfig::Any(cfg) => format!("any of {} matchers", cfg.any.len()),
+            MatcherConfig::Not(cfg) => format!("not {}", cfg.not.describe()),
+            MatcherConfig::List(list) => format!("list of {} matchers", list.len()),
+        }
+    }
+}
+
+/// Evaluates the top-level matcher of a config for the request in `ctx`. Its
+/// input is the request target, i.e. the path followed by the query.
+pub fn evaluate_request(matcher: &MatcherConfig, ctx: &mut Context) -> Result<Option<Resolution>> {
+    let target = ctx.request().target();
+    let resolution = matcher.evaluate_at("match", &target, ctx)?;
+    debug_assert!(
+        !matches!(resolution, Some(Resolution::Accepted {})),
+        "only conditions accept without resolving, and those are never at the top"
+    );
+    Ok(resolution)
+}
+
+/// Evaluates the top-level matcher of a config and records every step.
+pub fn explain(
+    matcher: &MatcherConfig,
+    request: Request,
+) -> (Result<Option<Resolution>>, Option<Step>) {
+    let mut ctx = Context::new(request).with_trace();
+    let result = evaluate_request(matcher, &mut ctx);
+    (result, ctx.into_trace())
+}
+
+/// Renders the config's `fallback` for a request that no matcher accepted.
+/// `$1` is the request path.
+pub fn render_fallback(template: &Template, ctx: &Context) -> String {
+    let path = ctx.request().path.as_str();
+    render_url(template, ctx, |name| (name == "1").then_some(path))
+}
+
+fn describe_source(source: Option<&InputSource>) -> String {
+    match source {
+        Some(InputSource::Query) => "query".to_string(),
+        Some(InputSource::Fragment) => "fragment".to_string(),
+        Some(InputSource::Param(name)) => format!("query parameter `{name}`"),
+        Some(InputSource::Path | InputSource::Target) | None => "input".to_string(),
+    }
+}
+
+/// Renders a `url` template with the matcher's own placeholders (`lookup`)
+/// and the ones every matcher has (e.g. `${query.q}`).
+fn render_url<'a>(
+    template: &Template,
+    ctx: &'a Context,
+    lookup: impl Fn(&str) -> Option<&'a str>,
+) -> String {
+    template.render_url(|name| lookup(name).or_else(|| ctx.placeholder(name)))
+}
+
+/// Like [`render_url`], but for templates that aren't URLs (e.g.
+/// `match-with`).
+fn render_text<'a>(
+    template: &Template,
+    ctx: &'a Context,
+    lookup: impl Fn(&str) -> Option<&'a str>,
+) -> String {
+    template.render_text(|name| lookup(name).or_else(|| ctx.placeholder(name)))
+}
+
+/// Compiles the optional sub matcher of a matcher config.
+fn compile_sub_matcher(matcher: &Option<Box<MatcherConfig>>) -> Result<()> {
+    match matcher {
+        Some(matcher) => matcher.compile(),
+        None => Ok(()),
     }
 }
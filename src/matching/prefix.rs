@@ -1,20 +1,20 @@
 use eyre::Result;
 use tracing::instrument;
 
-use super::Matcher;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
 use crate::config::PrefixMatcherConfig;
 
 impl Matcher for PrefixMatcherConfig {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
         tracing::info!(matcher = ?self, input, "running prefix matcher");
 
-        if input.len() < self.prefix.len() {
+        let Some(candidate_prefix) = input.get(..self.prefix.len()) else {
             tracing::info!("prefix matcher did not match");
+            ctx.reject(|| format!("input does not start with `{}`", self.prefix));
             return Ok(None);
-        }
+        };
 
-        let candidate_prefix = &input[..self.prefix.len()];
         let matches = if self.case_sensitive {
             candidate_prefix == self.prefix
         } else {
@@ -23,19 +23,28 @@ impl Matcher for PrefixMatcherConfig {
 
         if matches {
             let remainder = &input[self.prefix.len()..];
-            if let Some(url) = &self.url {
-                let redirect = url.replace("$1", candidate_prefix).replace("$2", remainder);
-                tracing::info!(%redirect, "prefix matcher produced redirect");
-                return Ok(Some(redirect));
+            if let Some(resolution) = self.target().resolve(ctx, |name| match name {
+                "1" => Some(candidate_prefix),
+                "2" => Some(remainder),
+                _ => None,
+            })? {
+                tracing::info!(%resolution, "prefix matcher resolved the request");
+                return Ok(Some(resolution));
             }
 
             if let Some(matcher) = &self.matcher {
                 tracing::info!("prefix matcher delegating to sub matcher");
-                return matcher.apply(remainder);
+                return matcher.evaluate_match(remainder, ctx);
             }
         }
 
         tracing::info!("prefix matcher did not match");
+        ctx.reject(|| format!("input does not start with `{}`", self.prefix));
         Ok(None)
     }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
 }
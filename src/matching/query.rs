@@ -0,0 +1,71 @@
+use eyre::{Result, bail};
+use regex::Regex;
+use tracing::instrument;
+
+use super::regex::build_regex;
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::QueryMatcherConfig;
+
+impl QueryMatcherConfig {
+    /// The compiled `value-regex`, built on first use.
+    fn value_regex(&self) -> Result<Option<&Regex>> {
+        let Some(pattern) = &self.value_regex else {
+            return Ok(None);
+        };
+        self.compiled
+            .get_or_try_init(|| build_regex(pattern, self.case_sensitive))
+            .map(Some)
+    }
+}
+
+impl Matcher for QueryMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running query matcher");
+
+        let Some(value) = ctx.param(&self.query).map(str::to_string) else {
+            tracing::info!("query matcher did not match");
+            ctx.reject(|| format!("the request has no query parameter `{}`", self.query));
+            return Ok(None);
+        };
+
+        let matches = if let Some(expected) = &self.value {
+            if self.case_sensitive {
+                &value == expected
+            } else {
+                value.eq_ignore_ascii_case(expected)
+            }
+        } else if let Some(regex) = self.value_regex()? {
+            regex.is_match(&value)
+        } else {
+            true
+        };
+
+        if matches {
+            if let Some(resolution) = self
+                .target()
+                .resolve(ctx, |name| (name == "1").then_some(&value))?
+            {
+                tracing::info!(%resolution, "query matcher resolved the request");
+                return Ok(Some(resolution));
+            }
+            if let Some(matcher) = &self.matcher {
+                tracing::info!("query matcher delegating to sub matcher");
+                return matcher.evaluate_match(&value, ctx);
+            }
+        }
+
+        tracing::info!("query matcher did not match");
+        ctx.reject(|| format!("`{value}` is not an accepted value of `{}`", self.query));
+        Ok(None)
+    }
+
+    fn compile(&self) -> Result<()> {
+        if self.value.is_some() && self.value_regex.is_some() {
+            bail!("use either `value` or `value-regex`, not both");
+        }
+        self.value_regex()?;
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use eyre::{Result, bail};
+use tracing::instrument;
+
+use super::{Context, Matcher, Resolution};
+use crate::config::{Config, MatcherConfig, RefMatcherConfig};
+
+impl RefMatcherConfig {
+    /// The definition this matcher refers to, once the config is linked.
+    pub fn target(&self) -> Option<&MatcherConfig> {
+        self.target.get().map(Arc::as_ref)
+    }
+}
+
+impl Matcher for RefMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running ref matcher");
+        let Some(target) = self.target() else {
+            bail!("definition `{}` is not linked", self.name);
+        };
+        target.evaluate_at(&format!("definitions.{}", self.name), input, ctx)
+    }
+}
+
+/// Links every `ref` matcher of the config to its definition.
+///
+/// Fails if a definition doesn't exist or if definitions refer to each other
+/// in a cycle, which would never stop evaluating.
+pub(crate) fn link(cfg: &Config) -> Result<()> {
+    let mut refs = Vec::new();
+    collect_refs(&cfg.matcher, "match".to_string(), &mut refs);
+    for (name, definition) in &cfg.definitions {
+        collect_refs(definition, format!("definitions.{name}"), &mut refs);
+    }
+
+    for (path, reference) in &refs {
+        if cfg.definitions.contains_key(&reference.name) {
+            continue;
+        }
+        let mut msg = format!("{path}: unknown definition `{}`", reference.name);
+        let suggestion = cfg
+            .definitions
+            .keys()
+            .find(|name| strsim::levenshtein(name, &reference.name) <= 2);
+        if let Some(name) = suggestion {
+            msg.push_str(&format!(" (did you mean `{name}`?)"));
+        }
+        bail!(msg);
+    }
+
+    for name in cfg.definitions.keys() {
+        check_cycle(&cfg.definitions, &mut vec![name.as_str()])?;
+    }
+
+    for (_, reference) in refs {
+        let target = Arc::clone(&cfg.definitions[&reference.name]);
+        reference
+            .target
+            .get_or_try_init(|| Ok::<_, std::convert::Infallible>(target))
+            .ok();
+    }
+    Ok(())
+}
+
+/// Follows the references of the last definition in `stack`.
+fn check_cycle<'c>(
+    definitions: &'c BTreeMap<String, Arc<MatcherConfig>>,
+    stack: &mut Vec<&'c str>,
+) -> Result<()> {
+    let name = stack[stack.len() - 1];
+    let mut refs = Vec::new();
+    collect_refs(&definitions[name], String::new(), &mut refs);
+    for (_, reference) in refs {
+        if let Some(start) = stack.iter().position(|n| *n == reference.name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(&reference.name);
+            bail!("reference cycle: {}", cycle.join(" -> "));
+        }
+        stack.push(&reference.name);
+        check_cycle(definitions, stack)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+/// Collects the `ref` matchers below `matcher` (without following them),
+/// along with their paths.
+fn collect_refs<'c>(
+    matcher: &'c MatcherConfig,
+    path: String,
+    refs: &mut Vec<(String, &'c RefMatcherConfig)>,
+) {
+    let sub = match matcher {
+        MatcherConfig::Exact(cfg) => &cfg.matcher,
+        MatcherConfig::Prefix(cfg) => &cfg.matcher,
+        MatcherConfig::Suffix(cfg) => &cfg.matcher,
+        MatcherConfig::Contains(cfg) => &cfg.matcher,
+        MatcherConfig::Fuzzy(cfg) => &cfg.matcher,
+        MatcherConfig::Glob(cfg) => &cfg.matcher,
+        MatcherConfig::Regex(cfg) => &cfg.matcher,
+        MatcherConfig::Query(cfg) => &cfg.matcher,
+        MatcherConfig::Command(cfg) => &cfg.matcher,
+        MatcherConfig::Ref(cfg) => {
+            refs.push((path, cfg));
+            return;
+        }
+        MatcherConfig::Keywords(cfg) => {
+            for (keyword, entry) in &cfg.keywords {
+                if let Some(sub) = &entry.matcher {
+                    collect_refs(sub, format!("{path}.keywords.{keyword}.match"), refs);
+                }
+            }
+            return;
+        }
+        MatcherConfig::All(cfg) => {
+            for (idx, item) in cfg.all.iter().enumerate() {
+                collect_refs(item, format!("{path}.all[{idx}]"), refs);
+            }
+            &cfg.matcher
+        }
+        MatcherConfig::Any(cfg) => {
+            for (idx, item) in cfg.any.iter().enumerate() {
+                collect_refs(item, format!("{path}.any[{idx}]"), refs);
+            }
+            return;
+        }
+        MatcherConfig::Not(cfg) => {
+            collect_refs(&cfg.not, format!("{path}.not"), refs);
+            &cfg.matcher
+        }
+        MatcherConfig::List(list) => {
+            for (idx, item) in list.iter().enumerate() {
+                collect_refs(item, format!("{path}[{idx}]"), refs);
+            }
+            return;
+        }
+    };
+    if let Some(sub) = sub {
+        collect_refs(sub, format!("{path}.match"), refs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::{Outcome, Request, explain};
+
+    const JIRA: &str = "definitions:
+  jira:
+  - regex: '^([a-z]+)-(\\d+)$'
+    url: https://jira.example/browse/${1|upper}-$2
+match:
+- prefix: work/
+  match:
+    ref: jira
+- prefix: w/
+  match:
+    ref: jira
+";
+
+    #[test]
+    fn references_reuse_definitions() {
+        let cfg = Config::parse(JIRA).unwrap();
+        let result = cfg.matcher.apply("w/ops-12").unwrap();
+        assert_eq!(result.unwrap(), "https://jira.example/browse/OPS-12");
+
+        let (result, trace) = explain(&cfg.matcher, Request::parse("work/ops-12"));
+        assert!(result.unwrap().is_some());
+        let step = &trace.unwrap().steps[0].steps[0];
+        assert_eq!(step.path, "match[0].match");
+        assert_eq!(step.matcher, "ref `jira`");
+        assert_eq!(step.steps[0].path, "definitions.jira");
+        assert_eq!(step.steps[0].steps[0].path, "definitions.jira[0]");
+        assert!(matches!(
+            step.steps[0].steps[0].outcome,
+            Outcome::Matched { .. }
+        ));
+    }
+
+    #[test]
+    fn unknown_definition() {
+        let input = JIRA.replace("ref: jira\n- prefix: w/", "ref: jra\n- prefix: w/");
+        let err = Config::parse(&input).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "match[0].match: unknown definition `jra` (did you mean `jira`?)"
+        );
+    }
+
+    #[test]
+    fn reference_cycle() {
+        let input = "definitions:
+  a:
+    prefix: a/
+    match:
+      ref: b
+  b:
+  - exact: x
+    url: https://x.example
+  - ref: a
+match:
+  ref: a
+";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert_eq!(err, "reference cycle: a -> b -> a");
+    }
+}
@@ -1,56 +1,92 @@
-use eyre::Result;
-use regex::RegexBuilder;
+use eyre::{Result, WrapErr};
+use regex::{Regex, RegexBuilder};
 use tracing::instrument;
 
-use super::Matcher;
+use super::{Context, Matcher, Resolution, Template, compile_sub_matcher, render_text};
 use crate::config::RegexMatcherConfig;
 
+impl RegexMatcherConfig {
+    /// The compiled regular expression, built on first use.
+    fn regex(&self) -> Result<&Regex> {
+        self.compiled
+            .get_or_try_init(|| build_regex(&self.regex, self.case_sensitive))
+    }
+
+    /// The parsed `match-with`, if the matcher has one.
+    fn match_with(&self) -> Result<Option<&Template>> {
+        let Some(match_with) = &self.match_with else {
+            return Ok(None);
+        };
+        self.compiled_match_with
+            .get_or_try_init(|| Template::parse(match_with))
+            .map(Some)
+    }
+}
+
+/// Compiles a regex from a matcher config.
+pub(super) fn build_regex(pattern: &str, case_sensitive: bool) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .wrap_err_with(|| format!("invalid regex `{pattern}`"))
+}
+
 impl Matcher for RegexMatcherConfig {
-    #[instrument(level = "info", skip(self, input))]
-    fn apply(&self, input: &str) -> Result<Option<String>> {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
         tracing::info!(matcher = ?self, input, "running regex matcher");
 
-        let regex = RegexBuilder::new(&self.regex)
-            .case_insensitive(!self.case_sensitive)
-            .build()?;
+        let regex = self.regex()?;
 
         let Some(caps) = regex.captures(input) else {
             tracing::info!("regex matcher did not match");
+            ctx.reject(|| "regex does not match the input".to_string());
             return Ok(None);
         };
 
         // Determine the value forwarded to the sub matcher or used for $1 placeholder
         let matched = caps.get(0).map(|m| m.as_str()).unwrap_or("");
-        let candidate = if let Some(template) = &self.match_with {
-            substitute_template(template, &caps)
+        let candidate = if let Some(match_with) = self.match_with()? {
+            render_text(match_with, ctx, capture(regex, &caps))
         } else {
             matched.to_string()
         };
 
-        if let Some(url) = &self.url {
-            let mut redirect = url.clone();
-            redirect = substitute_template(&redirect, &caps);
-            tracing::info!(%redirect, "regex matcher produced redirect");
-            return Ok(Some(redirect));
+        if let Some(resolution) = self.target().resolve(ctx, capture(regex, &caps))? {
+            tracing::info!(%resolution, "regex matcher resolved the request");
+            return Ok(Some(resolution));
         }
 
         if let Some(matcher) = &self.matcher {
             tracing::info!("regex matcher delegating to sub matcher");
-            return matcher.apply(&candidate);
+            return matcher.evaluate_match(&candidate, ctx);
         }
 
         tracing::info!("regex matcher did not match");
         Ok(None)
     }
+
+    fn compile(&self) -> Result<()> {
+        self.regex()?;
+        self.target().check()?;
+        self.match_with()?;
+        compile_sub_matcher(&self.matcher)
+    }
 }
 
-fn substitute_template(template: &str, caps: &regex::Captures) -> String {
-    let mut result = template.to_string();
-    for i in 0..caps.len() {
-        if let Some(m) = caps.get(i) {
-            let placeholder = format!("${i}");
-            result = result.replace(&placeholder, m.as_str());
-        }
+/// Looks up capture groups by index or name. Groups that didn't participate
+/// in the match are empty.
+pub(super) fn capture<'h>(
+    regex: &Regex,
+    caps: &regex::Captures<'h>,
+) -> impl Fn(&str) -> Option<&'h str> {
+    |name| {
+        let group = match name.parse::<usize>() {
+            Ok(idx) if idx < caps.len() => caps.get(idx),
+            Ok(_) => return None,
+            Err(_) if regex.capture_names().flatten().any(|n| n == name) => caps.name(name),
+            Err(_) => return None,
+        };
+        Some(group.map_or("", |m| m.as_str()))
     }
-    result
 }
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use eyre::{Result, bail};
+use serde::Serialize;
+
+use super::{Context, Template, render_text, render_url};
+use crate::config::{
+    AllMatcherConfig, CommandMatcherConfig, Compiled, ContainsMatcherConfig, ExactMatcherConfig,
+    FuzzyMatcherConfig, GlobMatcherConfig, KeywordConfig, NotMatcherConfig, PrefixMatcherConfig,
+    QueryMatcherConfig, RedirectStatus, RegexMatcherConfig, ResponseMode, SuffixMatcherConfig,
+};
+
+/// What a matcher resolved a request to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Resolution {
+    Redirect {
+        url: String,
+        /// The matcher's `status`, if it has one.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status: Option<RedirectStatus>,
+        /// The matcher's `response`, if it has one.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        response: Option<ResponseMode>,
+    },
+    Text {
+        text: String,
+    },
+    /// A condition of an `all` or `not` matcher accepted its input.
+    Accepted {},
+}
+
+impl Resolution {
+    /// The URL or text.
+    pub fn value(&self) -> &str {
+        match self {
+            Resolution::Redirect { url, .. } => url,
+            Resolution::Text { text } => text,
+            Resolution::Accepted {} => "",
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Redirect {
+                url,
+                status,
+                response,
+            } => {
+                write!(f, "redirect to {url}")?;
+                if let Some(status) = status {
+                    write!(f, " with status {}", status.code())?;
+                }
+                if *response == Some(ResponseMode::Interstitial) {
+                    write!(f, " after confirmation")?;
+                }
+                Ok(())
+            }
+            Resolution::Text { text } => write!(f, "respond with `{text}`"),
+            Resolution::Accepted {} => f.write_str("accept the input"),
+        }
+    }
+}
+
+/// The `url` or `text` of a matcher config, along with its response options.
+pub(super) struct Target<'c> {
+    pub url: &'c Option<String>,
+    pub text: &'c Option<String>,
+    /// The `url` or `text`, once parsed.
+    pub compiled: &'c Compiled<Template>,
+    pub status: Option<RedirectStatus>,
+    pub response: Option<ResponseMode>,
+    /// Whether the matcher has a sub matcher.
+    pub sub: bool,
+}
+
+impl<'c> Target<'c> {
+    /// The parsed `url` or `text`, if the matcher has either.
+    fn template(&self) -> Result<Option<&'c Template>> {
+        let Some(template) = self.url.as_ref().or(self.text.as_ref()) else {
+            return Ok(None);
+        };
+        self.compiled
+            .get_or_try_init(|| Template::parse(template))
+            .map(Some)
+    }
+
+    /// Renders the `url` or `text` of a matcher that accepted its input.
+    /// Returns `None` if the matcher has neither (i.e. it has a sub matcher).
+    /// A condition without either resolves to [`Resolution::Accepted`].
+    pub fn resolve<'a>(
+        &self,
+        ctx: &'a Context,
+        lookup: impl Fn(&str) -> Option<&'a str>,
+    ) -> Result<Option<Resolution>> {
+        if let Some(template) = self.template()? {
+            return Ok(Some(match self.url {
+                Some(_) => Resolution::Redirect {
+                    url: render_url(template, ctx, lookup),
+                    status: self.status,
+                    response: self.response,
+                },
+                None => Resolution::Text {
+                    text: render_text(template, ctx, lookup),
+                },
+            }));
+        }
+        if !self.sub && ctx.in_condition() {
+            return Ok(Some(Resolution::Accepted {}));
+        }
+        Ok(None)
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.url.is_some() && self.text.is_some() {
+            bail!("use either `url` or `text`, not both");
+        }
+        self.template().map(|_| ())
+    }
+}
+
+macro_rules! impl_target {
+    ($($config:ty),*) => {$(
+        impl $config {
+            pub(super) fn target(&self) -> Target<'_> {
+                Target {
+                    url: &self.common.url,
+                    text: &self.common.text,
+                    compiled: &self.common.compiled_target,
+                    status: self.common.status,
+                    response: self.common.response,
+                    sub: self.matcher.is_some(),
+                }
+            }
+        }
+    )*};
+}
+
+impl_target!(
+    ExactMatcherConfig,
+    PrefixMatcherConfig,
+    SuffixMatcherConfig,
+    ContainsMatcherConfig,
+    FuzzyMatcherConfig,
+    GlobMatcherConfig,
+    RegexMatcherConfig,
+    QueryMatcherConfig,
+    KeywordConfig,
+    CommandMatcherConfig,
+    AllMatcherConfig,
+    NotMatcherConfig
+);
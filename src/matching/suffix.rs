@@ -0,0 +1,84 @@
+use eyre::Result;
+use tracing::instrument;
+
+use super::{Context, Matcher, Resolution, compile_sub_matcher};
+use crate::config::SuffixMatcherConfig;
+
+impl Matcher for SuffixMatcherConfig {
+    #[instrument(level = "info", skip(self, input, ctx))]
+    fn evaluate(&self, input: &str, ctx: &mut Context) -> Result<Option<Resolution>> {
+        tracing::info!(matcher = ?self, input, "running suffix matcher");
+
+        let split = input.len().checked_sub(self.suffix.len());
+        let Some((rest, candidate_suffix)) =
+            split.and_then(|split| Some((input.get(..split)?, input.get(split..)?)))
+        else {
+            tracing::info!("suffix matcher did not match");
+            ctx.reject(|| format!("input does not end with `{}`", self.suffix));
+            return Ok(None);
+        };
+
+        let matches = if self.case_sensitive {
+            candidate_suffix == self.suffix
+        } else {
+            candidate_suffix.eq_ignore_ascii_case(&self.suffix)
+        };
+
+        if matches {
+            if let Some(resolution) = self.target().resolve(ctx, |name| match name {
+                "1" => Some(rest),
+                "2" => Some(candidate_suffix),
+                _ => None,
+            })? {
+                tracing::info!(%resolution, "suffix matcher resolved the request");
+                return Ok(Some(resolution));
+            }
+
+            if let Some(matcher) = &self.matcher {
+                tracing::info!("suffix matcher delegating to sub matcher");
+                return matcher.evaluate_match(rest, ctx);
+            }
+        }
+
+        tracing::info!("suffix matcher did not match");
+        ctx.reject(|| format!("input does not end with `{}`", self.suffix));
+        Ok(None)
+    }
+
+    fn compile(&self) -> Result<()> {
+        self.target().check()?;
+        compile_sub_matcher(&self.matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::matching::Matcher;
+
+    #[test]
+    fn strips_the_suffix() {
+        let cfg = Config::parse(
+            "match:
+- suffix: '!'
+  url: https://tickets.example/$1
+- suffix: .RS
+  match:
+    regex: ^\\w+$
+    url: https://code.example/search?q=$0
+",
+        )
+        .unwrap();
+        let apply = |input| cfg.matcher.apply(input).unwrap();
+        assert_eq!(
+            apply("12345!").as_deref(),
+            Some("https://tickets.example/12345")
+        );
+        assert_eq!(
+            apply("main.rs").as_deref(),
+            Some("https://code.example/search?q=main")
+        );
+        assert_eq!(apply("src/main.rs"), None);
+        assert_eq!(apply("s"), None);
+    }
+}
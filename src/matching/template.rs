@@ -0,0 +1,508 @@
+//! Placeholder substitution for `url` and `match-with` templates.
+//!
+//! Placeholders are written as `$1`, `$2`, ... or `${name}` and refer to
+//! values provided by the matcher. `$$` stands for a literal `$`. In URL
+//! templates, values are percent-encoded according to the part of the URL
+//! they end up in. `$raw1` inserts the value without encoding, and so does
+//! `${query}`, which is the query string as it was sent.
+//!
+//! The long form `${1|lower|replace:' ':'+'}` passes the value through a
+//! pipeline of filters before it is inserted.
+
+use std::borrow::Cow;
+
+use base64::Engine;
+use eyre::{Result, bail};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+/// Characters encoded in a query or fragment value: everything but the
+/// unreserved characters of RFC 3986.
+const QUERY: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Characters encoded in a path. `/` is kept so that a single placeholder can
+/// span multiple segments (e.g. `rust-lang/rust`).
+const PATH: &AsciiSet = &QUERY
+    .remove(b'/')
+    .remove(b':')
+    .remove(b'@')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=');
+
+/// The part of a URL that a placeholder is substituted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// Scheme, authority and path.
+    Path,
+    Query,
+    Fragment,
+    /// Not a URL at all (e.g. `match-with`); values are inserted verbatim.
+    Text,
+}
+
+impl Position {
+    /// The position after the character `c`, either literal or part of a raw
+    /// value.
+    fn advance(self, c: char) -> Position {
+        match (self, c) {
+            (Position::Path, '?') => Position::Query,
+            (Position::Path | Position::Query, '#') => Position::Fragment,
+            (position, _) => position,
+        }
+    }
+
+    fn encode(self, value: &str) -> String {
+        match self {
+            Position::Path | Position::Fragment => utf8_percent_encode(value, PATH).to_string(),
+            Position::Query => utf8_percent_encode(value, QUERY).to_string(),
+            Position::Text => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Filter {
+    Lower,
+    Upper,
+    Slug,
+    Base64,
+    /// Percent-encodes everything but unreserved characters, regardless of
+    /// the position. Implies `raw`.
+    UrlEncode,
+    /// Skips the position-dependent encoding.
+    Raw,
+    Replace(String, String),
+    /// Used when the placeholder is unknown or empty.
+    Default(String),
+}
+
+impl Filter {
+    fn parse(name: &str, args: Vec<String>, placeholder: &str) -> Result<Filter> {
+        let expected = match name {
+            "replace" => 2,
+            "default" => 1,
+            _ => 0,
+        };
+        if args.len() != expected {
+            bail!(
+                "filter `{name}` in `{placeholder}` takes {expected} argument(s), got {}",
+                args.len()
+            );
+        }
+        let mut args = args.into_iter();
+        Ok(match name {
+            "lower" => Filter::Lower,
+            "upper" => Filter::Upper,
+            "slug" => Filter::Slug,
+            "base64" => Filter::Base64,
+            "urlencode" => Filter::UrlEncode,
+            "raw" => Filter::Raw,
+            "replace" => Filter::Replace(args.next().unwrap(), args.next().unwrap()),
+            "default" => Filter::Default(args.next().unwrap()),
+            _ => bail!("unknown filter `{name}` in `{placeholder}`"),
+        })
+    }
+
+    fn apply<'v>(&self, value: Cow<'v, str>) -> Cow<'v, str> {
+        match self {
+            Filter::Lower => value.to_lowercase().into(),
+            Filter::Upper => value.to_uppercase().into(),
+            Filter::Slug => slug(&value).into(),
+            Filter::Base64 => base64::engine::general_purpose::STANDARD
+                .encode(value.as_bytes())
+                .into(),
+            Filter::UrlEncode => utf8_percent_encode(&value, QUERY).to_string().into(),
+            Filter::Raw => value,
+            Filter::Replace(from, to) => value.replace(from.as_str(), to).into(),
+            Filter::Default(default) if value.is_empty() => default.clone().into(),
+            Filter::Default(_) => value,
+        }
+    }
+}
+
+/// Lowercases `value` and joins its alphanumeric runs with `-`.
+fn slug(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Placeholder {
+    name: String,
+    filters: Vec<Filter>,
+    /// The placeholder as written, kept in the output if it can't be resolved.
+    source: String,
+}
+
+impl Placeholder {
+    fn is_raw(&self) -> bool {
+        // The query string is already encoded.
+        self.name == "query"
+            || self
+                .filters
+                .iter()
+                .any(|f| matches!(f, Filter::Raw | Filter::UrlEncode))
+    }
+
+    fn default(&self) -> Option<&str> {
+        self.filters.iter().find_map(|f| match f {
+            Filter::Default(default) => Some(default.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed template, e.g. the `url` of a matcher.
+///
+/// Templates are parsed when the configuration is loaded, which also checks
+/// that they are well-formed (e.g. don't use unknown filters).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub(crate) fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(idx) = rest.find('$') {
+            if idx > 0 {
+                parts.push(Part::Literal(rest[..idx].to_string()));
+            }
+            rest = &rest[idx..];
+            let (part, len) = match rest[1..].strip_prefix('{') {
+                Some(body) => parse_braced(rest, body)?,
+                None => parse_short(rest),
+            };
+            parts.push(part);
+            rest = &rest[len..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Template { parts })
+    }
+
+    fn render<'a>(
+        &self,
+        mut position: Position,
+        lookup: impl Fn(&str) -> Option<&'a str>,
+    ) -> String {
+        let mut result = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => {
+                    literal.chars().for_each(|c| position = position.advance(c));
+                    result.push_str(literal);
+                }
+                Part::Placeholder(placeholder) => {
+                    let value = match lookup(&placeholder.name) {
+                        Some(value) => Cow::Borrowed(value),
+                        None if placeholder.default().is_some() => Cow::Borrowed(""),
+                        None => {
+                            // Not a placeholder we know, keep it as written.
+                            result.push_str(&placeholder.source);
+                            continue;
+                        }
+                    };
+                    let value = placeholder
+                        .filters
+                        .iter()
+                        .fold(value, |value, filter| filter.apply(value));
+                    if placeholder.is_raw() {
+                        // A raw value can start the query or fragment itself.
+                        value.chars().for_each(|c| position = position.advance(c));
+                        result.push_str(&value);
+                    } else {
+                        result.push_str(&position.encode(&value));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Substitutes the placeholders of a URL template, encoding each value
+    /// for the part of the URL it is inserted into.
+    pub(crate) fn render_url<'a>(&self, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+        self.render(Position::Path, lookup)
+    }
+
+    /// Substitutes the placeholders of a template that is not a URL, without
+    /// any encoding.
+    pub(crate) fn render_text<'a>(&self, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+        self.render(Position::Text, lookup)
+    }
+}
+
+/// Parses a short placeholder like `$1` or `$raw1` (or the escape `$$`) at
+/// the start of `rest`. Returns the part and the number of bytes it spans.
+fn parse_short(rest: &str) -> (Part, usize) {
+    if rest[1..].starts_with('$') {
+        return (Part::Literal("$".to_string()), 2);
+    }
+    let (raw, name_start) = match rest[1..].strip_prefix("raw") {
+        Some(_) => (true, 4),
+        None => (false, 1),
+    };
+    let name_len = rest[name_start..]
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .count();
+    if name_len == 0 {
+        // A lone `$` is just a character.
+        return (Part::Literal("$".to_string()), 1);
+    }
+    let len = name_start + name_len;
+    let filters = if raw { vec![Filter::Raw] } else { vec![] };
+    let placeholder = Placeholder {
+        name: rest[name_start..len].to_string(),
+        filters,
+        source: rest[..len].to_string(),
+    };
+    (Part::Placeholder(placeholder), len)
+}
+
+/// Parses a placeholder like `${1|lower}` at the start of `rest`, where `body`
+/// is everything after the opening `${`. Returns the part and the number of
+/// bytes it spans.
+fn parse_braced(rest: &str, body: &str) -> Result<(Part, usize)> {
+    let unterminated = || eyre::eyre!("unterminated placeholder `{rest}`");
+    let name_len = body.find(['|', '}']).ok_or_else(unterminated)?;
+    let name = &body[..name_len];
+    let mut cursor = &body[name_len..];
+    let mut filters = Vec::new();
+    let mut pending = Vec::new();
+    while let Some(c) = cursor.chars().next() {
+        if c == '}' {
+            break;
+        }
+        // `c` is either `|` (start of a filter) or `:` (start of an argument).
+        let (token, remainder) = take_token(&cursor[1..]).ok_or_else(unterminated)?;
+        match c {
+            '|' => pending.push((token, Vec::new())),
+            _ => pending.last_mut().ok_or_else(unterminated)?.1.push(token),
+        }
+        cursor = remainder;
+    }
+    if cursor.is_empty() {
+        return Err(unterminated());
+    }
+    let len = rest.len() - cursor.len() + 1;
+    let source = &rest[..len];
+    if name.is_empty() {
+        bail!("missing placeholder name in `{source}`");
+    }
+    for (filter, args) in pending {
+        filters.push(Filter::parse(&filter, args, source)?);
+    }
+    let placeholder = Placeholder {
+        name: name.to_string(),
+        filters,
+        source: source.to_string(),
+    };
+    Ok((Part::Placeholder(placeholder), len))
+}
+
+/// Reads a filter name or argument, which is either quoted in `'` or ends at
+/// the next `|`, `:` or `}`.
+fn take_token(input: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = input.strip_prefix('\'') {
+        let end = quoted.find('\'')?;
+        return Some((quoted[..end].to_string(), &quoted[end + 1..]));
+    }
+    let end = input.find(['|', ':', '}'])?;
+    Some((input[..end].to_string(), &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(template: &str) -> Result<()> {
+        Template::parse(template).map(|_| ())
+    }
+
+    fn render_url<'a>(template: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> Result<String> {
+        Ok(Template::parse(template)?.render_url(lookup))
+    }
+
+    fn render_text<'a>(template: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> Result<String> {
+        Ok(Template::parse(template)?.render_text(lookup))
+    }
+
+    fn numbered<'a>(values: &'a [&'a str]) -> impl Fn(&str) -> Option<&'a str> {
+        |name| {
+            let idx: usize = name.parse().ok()?;
+            values.get(idx).copied()
+        }
+    }
+
+    #[test]
+    fn encodes_query_values() {
+        let url = render_url(
+            "https://example.com/search?q=$1",
+            numbered(&["", "c++ & rust"]),
+        );
+        assert_eq!(
+            url.unwrap(),
+            "https://example.com/search?q=c%2B%2B%20%26%20rust"
+        );
+    }
+
+    #[test]
+    fn encodes_path_values() {
+        let url = render_url("https://example.com/$1?x=1", numbered(&["", "a b/c?d"]));
+        assert_eq!(url.unwrap(), "https://example.com/a%20b/c%3Fd?x=1");
+    }
+
+    #[test]
+    fn encodes_fragment_values() {
+        let url = render_url("https://example.com/?q=1#$1", numbered(&["", "a b#c"]));
+        assert_eq!(url.unwrap(), "https://example.com/?q=1#a%20b%23c");
+    }
+
+    #[test]
+    fn raw_values_are_not_encoded() {
+        let url = render_url("https://example.com?$raw1", numbered(&["", "a=1&b=2"]));
+        assert_eq!(url.unwrap(), "https://example.com?a=1&b=2");
+    }
+
+    #[test]
+    fn raw_values_advance_the_position() {
+        let url = render_url(
+            "https://example.com/$raw1?x=$2",
+            numbered(&["", "search?q=a", "b c/d"]),
+        );
+        assert_eq!(url.unwrap(), "https://example.com/search?q=a?x=b%20c%2Fd");
+        let url = render_url(
+            "https://example.com/$raw1$2",
+            numbered(&["", "page#", "a b/c"]),
+        );
+        assert_eq!(url.unwrap(), "https://example.com/page#a%20b/c");
+    }
+
+    #[test]
+    fn multi_digit_placeholders() {
+        let values = ["", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let url = render_url("https://example.com/$10/$1", numbered(&values));
+        assert_eq!(url.unwrap(), "https://example.com/j/a");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        let url = render_url("https://example.com/$3/$x/${4}/$", numbered(&["", "a"]));
+        assert_eq!(url.unwrap(), "https://example.com/$3/$x/${4}/$");
+    }
+
+    #[test]
+    fn named_placeholders() {
+        let lookup = |name: &str| (name == "ticket").then_some("AB-12");
+        let url = render_url("https://jira.example/browse/${ticket}", lookup);
+        assert_eq!(url.unwrap(), "https://jira.example/browse/AB-12");
+    }
+
+    #[test]
+    fn dollar_escape() {
+        let url = render_url("https://x.example/?price=$$$1&a=$$1", numbered(&["", "5"]));
+        assert_eq!(url.unwrap(), "https://x.example/?price=$5&a=$1");
+    }
+
+    #[test]
+    fn text_is_not_encoded() {
+        let text = render_text("$1.$2", numbered(&["", "a b", "c?"]));
+        assert_eq!(text.unwrap(), "a b.c?");
+    }
+
+    #[test]
+    fn case_filters() {
+        let url = render_url(
+            "https://x.example/${1|lower}/${1|upper}",
+            numbered(&["", "MiX"]),
+        );
+        assert_eq!(url.unwrap(), "https://x.example/mix/MIX");
+    }
+
+    #[test]
+    fn slug_filter() {
+        let url = render_url(
+            "https://x.example/${1|slug}",
+            numbered(&["", " Hello, World! "]),
+        );
+        assert_eq!(url.unwrap(), "https://x.example/hello-world");
+    }
+
+    #[test]
+    fn base64_filter_is_encoded_for_position() {
+        let url = render_url("https://x.example/?d=${1|base64}", numbered(&["", "a?>"]));
+        assert_eq!(url.unwrap(), "https://x.example/?d=YT8%2B");
+    }
+
+    #[test]
+    fn urlencode_filter_encodes_slashes() {
+        let url = render_url("https://x.example/${1|urlencode}", numbered(&["", "a/b c"]));
+        assert_eq!(url.unwrap(), "https://x.example/a%2Fb%20c");
+    }
+
+    #[test]
+    fn replace_filter_with_quoted_arguments() {
+        let url = render_url(
+            "https://x.example/?q=${1|replace:' ':'+'|raw}",
+            numbered(&["", "a b c"]),
+        );
+        assert_eq!(url.unwrap(), "https://x.example/?q=a+b+c");
+    }
+
+    #[test]
+    fn default_filter() {
+        let values = ["", "repo", ""];
+        let url = render_url(
+            "https://x.example/$1/tree/${2|default:main}/${3|default:x}",
+            numbered(&values),
+        );
+        assert_eq!(url.unwrap(), "https://x.example/repo/tree/main/x");
+    }
+
+    #[test]
+    fn unknown_filter_is_an_error() {
+        let err = check("https://x.example/${1|shout}").unwrap_err();
+        assert_eq!(err.to_string(), "unknown filter `shout` in `${1|shout}`");
+    }
+
+    #[test]
+    fn unterminated_placeholder_is_an_error() {
+        let err = check("https://x.example/${1|lower").unwrap_err();
+        assert_eq!(err.to_string(), "unterminated placeholder `${1|lower`");
+    }
+
+    #[test]
+    fn wrong_argument_count_is_an_error() {
+        let err = check("${1|replace:a}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "filter `replace` in `${1|replace:a}` takes 2 argument(s), got 1"
+        );
+    }
+}
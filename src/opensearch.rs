@@ -0,0 +1,216 @@
+//! OpenSearch descriptions, which let browsers add the daemon as a search
+//! engine.
+
+use crate::config::MatcherConfig;
+use crate::html::{LINK, escape};
+use crate::shortcuts::{self, Literal};
+
+/// The MIME type of an OpenSearch description.
+pub const CONTENT_TYPE: &str = "application/opensearchdescription+xml";
+
+/// The MIME type of OpenSearch suggestions.
+pub const SUGGESTIONS_CONTENT_TYPE: &str = "application/x-suggestions+json";
+
+/// A search engine the daemon offers: either everything, or a single prefix
+/// (e.g. `gh `) so the browser only needs the rest of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Engine {
+    /// The prefix, empty for the engine that covers every shortcut.
+    pub prefix: String,
+    pub name: String,
+}
+
+impl Engine {
+    /// The engine that covers every shortcut.
+    pub fn all() -> Self {
+        Engine {
+            prefix: String::new(),
+            name: "shortcut-catapult".to_string(),
+        }
+    }
+
+    /// Where the daemon serves the description, relative to its root.
+    pub fn path(&self) -> String {
+        if self.prefix.is_empty() {
+            return "/_opensearch.xml".to_string();
+        }
+        let prefix =
+            percent_encoding::utf8_percent_encode(&self.prefix, percent_encoding::NON_ALPHANUMERIC);
+        format!("/_opensearch/{prefix}.xml")
+    }
+
+    /// Renders the description for a daemon running at `base`, e.g.
+    /// `http://localhost:8081`.
+    pub fn description(&self, base: &str) -> String {
+        // Browsers replace `{searchTerms}` with the percent-encoded input.
+        let prefix = percent_encoding::utf8_percent_encode(&self.prefix, LINK);
+        let template = format!("{base}/{prefix}{{searchTerms}}");
+        let suggest = match self.prefix.as_str() {
+            "" => format!("{base}/_suggest?q={{searchTerms}}"),
+            prefix => {
+                let prefix = percent_encoding::utf8_percent_encode(
+                    prefix,
+                    percent_encoding::NON_ALPHANUMERIC,
+                );
+                format!("{base}/_suggest?prefix={prefix}&q={{searchTerms}}")
+            }
+        };
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n\
+             <ShortName>{}</ShortName>\n\
+             <Description>{}</Description>\n\
+             <InputEncoding>UTF-8</InputEncoding>\n\
+             <Url type=\"text/html\" method=\"get\" template=\"{}\"/>\n\
+             <Url type=\"{SUGGESTIONS_CONTENT_TYPE}\" method=\"get\" template=\"{}\"/>\n\
+             </OpenSearchDescription>\n",
+            escape(&short_name(&self.name)),
+            escape(&self.name),
+            escape(&template),
+            escape(&suggest),
+        )
+    }
+}
+
+/// Builds the suggestions response for `query` typed into the search engine
+/// for `prefix`: the query, the completions, their descriptions and the URLs
+/// they lead to on the daemon at `base`.
+pub fn suggestions(
+    query: &str,
+    prefix: &str,
+    completions: &[Literal],
+    base: &str,
+) -> serde_json::Value {
+    let mut texts = Vec::new();
+    let mut descriptions = Vec::new();
+    let mut urls = Vec::new();
+    for literal in completions {
+        // Completing to the prefix itself would leave the search box empty.
+        let Some(text) =
+            strip_prefix_ignore_case(&literal.input, prefix).filter(|text| !text.is_empty())
+        else {
+            continue;
+        };
+        texts.push(text.to_string());
+        let description = literal.description.as_ref().or(literal.name.as_ref());
+        descriptions.push(description.cloned().unwrap_or_default());
+        let path = percent_encoding::utf8_percent_encode(&literal.input, LINK);
+        urls.push(format!("{base}/{path}"));
+    }
+    serde_json::json!([query, texts, descriptions, urls])
+}
+
+/// `text` without `prefix`, which is compared case-insensitively like the
+/// completions are.
+fn strip_prefix_ignore_case<'t>(text: &'t str, prefix: &str) -> Option<&'t str> {
+    let mut chars = text.char_indices();
+    for expected in prefix.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+/// The OpenSearch spec limits the short name to 16 characters.
+fn short_name(name: &str) -> String {
+    name.chars().take(16).collect()
+}
+
+/// The engine for every shortcut, followed by one per `prefix` matcher.
+pub fn engines(matcher: &MatcherConfig) -> Vec<Engine> {
+    let mut engines = vec![Engine::all()];
+    engines.extend(
+        shortcuts::literals(matcher)
+            .into_iter()
+            .filter(|literal| literal.prefix)
+            .map(|literal| Engine {
+                name: literal
+                    .name
+                    .unwrap_or_else(|| literal.input.trim().to_string()),
+                prefix: literal.input,
+            }),
+    );
+    engines
+}
+
+/// The engine for `prefix`, if the config has a `prefix` matcher for it.
+pub fn engine(matcher: &MatcherConfig, prefix: &str) -> Option<Engine> {
+    engines(matcher)
+        .into_iter()
+        .skip(1)
+        .find(|engine| engine.prefix == prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn prefix_engines() {
+        let input = "match:
+- prefix: 'gh '
+  name: GitHub repositories
+  url: https://github.com/$2
+- prefix: animals/
+  match:
+    exact: bear
+    url: https://bears.org
+";
+        let cfg = Config::parse(input).unwrap();
+        let engines = engines(&cfg.matcher);
+        let paths: Vec<_> = engines.iter().map(Engine::path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/_opensearch.xml",
+                "/_opensearch/gh%20.xml",
+                "/_opensearch/animals%2F.xml"
+            ]
+        );
+
+        let xml = engines[1].description("http://localhost:8081");
+        assert!(
+            xml.contains("<ShortName>GitHub repositor</ShortName>"),
+            "{xml}"
+        );
+        assert!(
+            xml.contains(r#"template="http://localhost:8081/gh%20{searchTerms}""#),
+            "{xml}"
+        );
+        assert_eq!(engine(&cfg.matcher, "animals/"), Some(engines[2].clone()));
+        assert_eq!(engine(&cfg.matcher, "cats/"), None);
+    }
+
+    #[test]
+    fn suggestions_strip_the_prefix() {
+        let literal = |input: &str, description: Option<&str>| Literal {
+            input: input.to_string(),
+            prefix: false,
+            name: None,
+            description: description.map(str::to_string),
+        };
+        let completions = [
+            literal("animals/bear", Some("Bears")),
+            literal("animals/big cat", None),
+            literal("Animals/bison", None),
+            literal("gh ", None),
+        ];
+        let json = suggestions("b", "animals/", &completions, "http://localhost:8081");
+        assert_eq!(
+            json,
+            serde_json::json!([
+                "b",
+                ["bear", "big cat", "bison"],
+                ["Bears", "", ""],
+                [
+                    "http://localhost:8081/animals/bear",
+                    "http://localhost:8081/animals/big%20cat",
+                    "http://localhost:8081/Animals/bison"
+                ]
+            ])
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! The JSON Schema of the configuration file, for editors.
+
+use color_eyre::eyre::Result;
+use schemars::Schema;
+use tracing::instrument;
+
+use crate::{cli::SchemaArgs, config::Config};
+
+/// The schema of [`Config`], generated from its types.
+pub fn schema() -> Schema {
+    schemars::schema_for!(Config)
+}
+
+#[instrument(level = "debug", skip(_args))]
+pub fn run(_args: SchemaArgs) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&schema())?);
+    Ok(())
+}
@@ -0,0 +1,500 @@
+//! The shortcuts of a config, as listed on the daemon's landing page.
+
+use serde::Serialize;
+
+use crate::config::{AnyMatcherConfig, CommonConfig, Config, MatcherConfig};
+
+/// A matcher that resolves requests itself or is documented with a `name`,
+/// `description` or examples.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Shortcut {
+    /// Where the matcher is in the config, e.g. `match[3].match`.
+    pub path: String,
+    /// Descriptions of the matchers from the top level down to this one,
+    /// skipping lists.
+    pub matchers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    /// The `url` template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The `text` template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl Shortcut {
+    /// The `name`, or the matchers that lead to this shortcut.
+    pub fn title(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.matchers.join(" → "),
+        }
+    }
+}
+
+/// The fields of a matcher config that matter for the shortcut list.
+struct Node<'c> {
+    common: &'c CommonConfig,
+    matcher: &'c Option<Box<MatcherConfig>>,
+}
+
+macro_rules! node {
+    ($cfg:expr) => {
+        Node {
+            common: &$cfg.common,
+            matcher: &$cfg.matcher,
+        }
+    };
+}
+
+/// Lists the shortcuts of a config in config order: the ones of the matcher
+/// tree first, then the ones of the `definitions`.
+pub fn collect(cfg: &Config) -> Vec<Shortcut> {
+    let mut shortcuts = Vec::new();
+    walk(
+        &cfg.matcher,
+        "match".to_string(),
+        &mut Vec::new(),
+        &mut shortcuts,
+    );
+    for (name, definition) in &cfg.definitions {
+        walk(
+            definition,
+            format!("definitions.{name}"),
+            &mut Vec::new(),
+            &mut shortcuts,
+        );
+    }
+    shortcuts
+}
+
+fn walk(
+    matcher: &MatcherConfig,
+    path: String,
+    matchers: &mut Vec<String>,
+    shortcuts: &mut Vec<Shortcut>,
+) {
+    let node = match matcher {
+        MatcherConfig::Exact(cfg) => node!(cfg),
+        MatcherConfig::Prefix(cfg) => node!(cfg),
+        MatcherConfig::Suffix(cfg) => node!(cfg),
+        MatcherConfig::Contains(cfg) => node!(cfg),
+        MatcherConfig::Fuzzy(cfg) => node!(cfg),
+        MatcherConfig::Glob(cfg) => node!(cfg),
+        MatcherConfig::Regex(cfg) => node!(cfg),
+        MatcherConfig::Query(cfg) => node!(cfg),
+        MatcherConfig::Command(cfg) => node!(cfg),
+        // The definition is listed on its own.
+        MatcherConfig::Ref(_) => return,
+        MatcherConfig::Keywords(cfg) => {
+            matchers.push(matcher.describe());
+            for (keyword, entry) in &cfg.keywords {
+                visit(
+                    node!(entry),
+                    format!("keyword `{keyword}`"),
+                    format!("{path}.keywords.{keyword}"),
+                    matchers,
+                    shortcuts,
+                );
+            }
+            matchers.pop();
+            return;
+        }
+        MatcherConfig::All(cfg) => {
+            // The last condition may resolve the request itself.
+            matchers.push(matcher.describe());
+            for (idx, item) in cfg.all.iter().enumerate() {
+                walk(item, format!("{path}.all[{idx}]"), matchers, shortcuts);
+            }
+            matchers.pop();
+            node!(cfg)
+        }
+        // The matcher of a `not` never resolves anything.
+        MatcherConfig::Not(cfg) => node!(cfg),
+        MatcherConfig::Any(AnyMatcherConfig { any: list, .. }) => {
+            for (idx, item) in list.iter().enumerate() {
+                walk(item, format!("{path}.any[{idx}]"), matchers, shortcuts);
+            }
+            return;
+        }
+        MatcherConfig::List(list) => {
+            for (idx, item) in list.iter().enumerate() {
+                walk(item, format!("{path}[{idx}]"), matchers, shortcuts);
+            }
+            return;
+        }
+    };
+    visit(node, matcher.describe(), path, matchers, shortcuts);
+}
+
+/// Lists the matcher described by `node` (if it's a shortcut) and its sub
+/// matchers.
+fn visit(
+    node: Node<'_>,
+    description: String,
+    path: String,
+    matchers: &mut Vec<String>,
+    shortcuts: &mut Vec<Shortcut>,
+) {
+    matchers.push(description);
+    let common = node.common;
+    let documented = common.name.is_some()
+        || common.description.is_some()
+        || common.example.is_some()
+        || !common.examples.is_empty();
+    if documented || common.url.is_some() || common.text.is_some() {
+        shortcuts.push(Shortcut {
+            path: path.clone(),
+            matchers: matchers.clone(),
+            name: common.name.clone(),
+            description: common.description.clone(),
+            example: common.example.clone(),
+            examples: common.examples.clone(),
+            url: common.url.clone(),
+            text: common.text.clone(),
+        });
+    }
+    if let Some(sub) = node.matcher {
+        walk(sub, format!("{path}.match"), matchers, shortcuts);
+    }
+    matchers.pop();
+}
+
+/// The full input an `exact`, `prefix` or `fuzzy` matcher is written for,
+/// e.g. `animals/bear` for an `exact` below a `prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Literal {
+    pub input: String,
+    /// Whether the matcher is a `prefix`, so it accepts anything that starts
+    /// with `input`.
+    pub prefix: bool,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Up to `limit` inputs that the literal matchers in the tree are written for,
+/// closest to `input` first.
+pub fn suggestions(matcher: &MatcherConfig, input: &str, limit: usize) -> Vec<String> {
+    let input = input.to_lowercase();
+    let mut ranked: Vec<_> = literals(matcher)
+        .into_iter()
+        .map(|literal| {
+            (
+                strsim::levenshtein(&input, &literal.input.to_lowercase()),
+                literal.input,
+            )
+        })
+        .collect();
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked
+        .into_iter()
+        .map(|(_, input)| input)
+        .take(limit)
+        .collect()
+}
+
+/// Up to `limit` literals that complete `input` as it's being typed: the ones
+/// that start with it, followed by the ones that start with something close
+/// to it (like a typo), closest first.
+pub fn completions(matcher: &MatcherConfig, input: &str, limit: usize) -> Vec<Literal> {
+    let input = input.to_lowercase();
+    let (mut completions, rest): (Vec<_>, Vec<_>) = literals(matcher)
+        .into_iter()
+        .partition(|literal| literal.input.to_lowercase().starts_with(&input));
+
+    // Very short inputs are close to almost everything.
+    let length = input.chars().count();
+    if length >= NEIGHBOUR_MIN_LENGTH {
+        let mut neighbours: Vec<_> = rest
+            .into_iter()
+            .filter_map(|literal| {
+                let lower = literal.input.to_lowercase();
+                // The input may be missing a character or have one too many.
+                let distance = (length - 1..=length + 1)
+                    .map(|len| {
+                        let start: String = lower.chars().take(len).collect();
+                        strsim::levenshtein(&input, &start)
+                    })
+                    .min()?;
+                (distance <= NEIGHBOUR_DISTANCE).then_some((distance, literal))
+            })
+            .collect();
+        neighbours.sort_by_key(|(distance, _)| *distance);
+        completions.extend(neighbours.into_iter().map(|(_, literal)| literal));
+    }
+
+    completions.truncate(limit);
+    completions
+}
+
+/// How many edits an input may be away from the start of a literal to still
+/// be completed to it.
+const NEIGHBOUR_DISTANCE: usize = 2;
+const NEIGHBOUR_MIN_LENGTH: usize = 3;
+
+/// The literals of the tree in config order, without duplicate inputs.
+pub fn literals(matcher: &MatcherConfig) -> Vec<Literal> {
+    let mut literals = Vec::new();
+    collect_literals(matcher, "", &mut literals);
+    literals
+}
+
+/// Matchers that don't work on the input their parent forwards (e.g. because
+/// of `input`) are skipped.
+fn collect_literals(matcher: &MatcherConfig, parent: &str, literals: &mut Vec<Literal>) {
+    let (literal, forwarded, sub) = match matcher {
+        MatcherConfig::Exact(cfg) if cfg.input.is_none() => {
+            let literal = Literal {
+                input: format!("{parent}{}", cfg.exact),
+                prefix: false,
+                name: cfg.common.name.clone(),
+                description: cfg.common.description.clone(),
+            };
+            (literal, parent.to_string(), &cfg.matcher)
+        }
+        MatcherConfig::Prefix(cfg) if cfg.input.is_none() => {
+            let literal = Literal {
+                input: format!("{parent}{}", cfg.prefix),
+                prefix: true,
+                name: cfg.common.name.clone(),
+                description: cfg.common.description.clone(),
+            };
+            let forwarded = literal.input.clone();
+            (literal, forwarded, &cfg.matcher)
+        }
+        MatcherConfig::Fuzzy(cfg) if cfg.input.is_none() => {
+            let literal = Literal {
+                input: format!("{parent}{}", cfg.fuzzy),
+                prefix: false,
+                name: cfg.common.name.clone(),
+                description: cfg.common.description.clone(),
+            };
+            (literal, parent.to_string(), &cfg.matcher)
+        }
+        MatcherConfig::Command(cfg) if cfg.input.is_none() => {
+            // Commands with arguments are followed by a space.
+            let takes_arguments = !cfg.args.is_empty() || !cfg.flags.is_empty();
+            let separator = if takes_arguments { " " } else { "" };
+            let literal = Literal {
+                input: format!("{parent}{}{separator}", cfg.command),
+                prefix: takes_arguments,
+                name: cfg.common.name.clone(),
+                description: cfg.common.description.clone(),
+            };
+            let forwarded = literal.input.clone();
+            (literal, forwarded, &cfg.matcher)
+        }
+        MatcherConfig::Any(AnyMatcherConfig {
+            any: list,
+            input: None,
+        })
+        | MatcherConfig::List(list) => {
+            for item in list {
+                collect_literals(item, parent, literals);
+            }
+            return;
+        }
+        MatcherConfig::Ref(cfg) => {
+            if let Some(target) = cfg.target() {
+                collect_literals(target, parent, literals);
+            }
+            return;
+        }
+        MatcherConfig::Keywords(cfg) if cfg.input.is_none() => {
+            // In argument mode, the keyword is followed by a space.
+            let separator = if cfg.argument { " " } else { "" };
+            for (keyword, entry) in &cfg.keywords {
+                for word in std::iter::once(keyword).chain(&entry.aliases) {
+                    let literal = Literal {
+                        input: format!("{parent}{word}{separator}"),
+                        prefix: cfg.argument,
+                        name: entry.common.name.clone(),
+                        description: entry.common.description.clone(),
+                    };
+                    let forwarded = if cfg.argument {
+                        literal.input.clone()
+                    } else {
+                        parent.to_string()
+                    };
+                    push_literal(literal, &forwarded, &entry.matcher, literals);
+                }
+            }
+            return;
+        }
+        _ => return,
+    };
+    push_literal(literal, &forwarded, sub, literals);
+}
+
+/// Adds a literal, unless it's empty or already known, followed by the ones
+/// of its sub matcher, which gets the `forwarded` input.
+fn push_literal(
+    literal: Literal,
+    forwarded: &str,
+    sub: &Option<Box<MatcherConfig>>,
+    literals: &mut Vec<Literal>,
+) {
+    let duplicate = literals.iter().any(|l| l.input == literal.input);
+    if !literal.input.is_empty() && !duplicate {
+        literals.push(literal);
+    }
+    if let Some(sub) = sub {
+        collect_literals(sub, forwarded, literals);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_documented_and_resolving_matchers() {
+        let input = "match:
+- exact: phone
+  text: '555'
+- prefix: animals/
+  description: Animal pages
+  match:
+  - exact: bear
+    name: Bears
+    example: animals/bear
+    url: https://bears.org
+  - fuzzy: lion
+    match:
+      exact: lion
+      url: https://lions.org
+";
+        let cfg = Config::parse(input).unwrap();
+        let shortcuts = collect(&cfg);
+        let summary: Vec<_> = shortcuts
+            .iter()
+            .map(|s| (s.path.as_str(), s.title()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("match[0]", "exact `phone`".to_string()),
+                ("match[1]", "prefix `animals/`".to_string()),
+                ("match[1].match[0]", "Bears".to_string()),
+                (
+                    "match[1].match[1].match",
+                    "prefix `animals/` → fuzzy `lion` (tolerance 3) → exact `lion`".to_string()
+                ),
+            ]
+        );
+        assert_eq!(shortcuts[0].text.as_deref(), Some("555"));
+        assert_eq!(shortcuts[2].example.as_deref(), Some("animals/bear"));
+    }
+
+    #[test]
+    fn suggestions_are_ranked_by_distance() {
+        let input = "match:
+- exact: phone
+  text: '555'
+- prefix: animals/
+  match:
+  - exact: bear
+    url: https://bears.org
+  - fuzzy: lion
+    url: https://lions.org
+- regex: '^gh (.*)$'
+  url: https://github.com/$1
+- exact: search
+  input: query.q
+  url: https://example.com
+";
+        let cfg = Config::parse(input).unwrap();
+        assert_eq!(
+            suggestions(&cfg.matcher, "Animals/beer", 3),
+            ["animals/bear", "animals/", "animals/lion"]
+        );
+        assert_eq!(suggestions(&cfg.matcher, "fone", 1), ["phone"]);
+    }
+
+    #[test]
+    fn completions_start_with_input_then_neighbours() {
+        let input = "match:
+- prefix: 'gh '
+  description: GitHub search
+  url: https://github.com/search?q=$2
+- exact: ghost
+  url: https://ghost.org
+- prefix: animals/
+  match:
+  - exact: bear
+    name: Bears
+    url: https://bears.org
+  - exact: lion
+    url: https://lions.org
+";
+        let cfg = Config::parse(input).unwrap();
+        let inputs = |input: &str| -> Vec<String> {
+            completions(&cfg.matcher, input, 10)
+                .into_iter()
+                .map(|literal| literal.input)
+                .collect()
+        };
+        assert_eq!(inputs("gh"), ["gh ", "ghost"]);
+        assert_eq!(
+            inputs("Animals/"),
+            ["animals/", "animals/bear", "animals/lion"]
+        );
+        assert_eq!(
+            inputs("anmals/b"),
+            ["animals/bear", "animals/", "animals/lion"]
+        );
+        assert_eq!(inputs("xy"), Vec::<String>::new());
+
+        let first = &completions(&cfg.matcher, "gh", 1)[0];
+        assert_eq!(first.description.as_deref(), Some("GitHub search"));
+    }
+
+    #[test]
+    fn keyword_entries_are_shortcuts_and_literals() {
+        let input = "match:
+  keywords:
+    gh:
+      aliases: [github]
+      name: GitHub search
+      example: gh rust
+      url: https://github.com/search?q=$1
+    mail:
+      url: https://mail.example.com
+  argument: true
+";
+        let cfg = Config::parse(input).unwrap();
+        let summary: Vec<_> = collect(&cfg)
+            .iter()
+            .map(|s| (s.path.clone(), s.title()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("match.keywords.gh".to_string(), "GitHub search".to_string()),
+                (
+                    "match.keywords.mail".to_string(),
+                    "2 keywords → keyword `mail`".to_string()
+                ),
+            ]
+        );
+        let inputs: Vec<_> = literals(&cfg.matcher)
+            .into_iter()
+            .map(|literal| (literal.input, literal.prefix))
+            .collect();
+        assert_eq!(
+            inputs,
+            [
+                ("gh ".to_string(), true),
+                ("github ".to_string(), true),
+                ("mail ".to_string(), true),
+            ]
+        );
+    }
+}
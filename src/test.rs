@@ -0,0 +1,220 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+use tracing::{info, instrument};
+
+use crate::{
+    cli::TestArgs,
+    config::{Config, Expected},
+    matching::{self, Context, Outcome, Request, Resolution, Step},
+    shortcuts,
+};
+
+/// An embedded test case or example that doesn't hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Where the test is in the config, e.g. `tests[2]` or
+    /// `match[1].examples[0]`.
+    pub path: String,
+    pub input: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "FAIL {}: {}", self.path, self.input)?;
+        writeln!(f, "  - {}", self.expected)?;
+        write!(f, "  + {}", self.actual)
+    }
+}
+
+/// The results of running the tests embedded in a config.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for failure in &self.failures {
+            writeln!(f, "{failure}")?;
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+impl Report {
+    fn record(&mut self, passed: bool, failure: impl FnOnce() -> Failure) {
+        if passed {
+            self.passed += 1;
+        } else {
+            self.failures.push(failure());
+        }
+    }
+}
+
+/// Runs the top-level `tests` and the `example`/`examples` of every matcher.
+pub fn run_tests(cfg: &Config) -> Report {
+    let mut report = Report::default();
+
+    for (idx, test) in cfg.tests.iter().enumerate() {
+        let mut ctx = Context::new(Request::from_input(&test.input));
+        let result = matching::evaluate_request(&cfg.matcher, &mut ctx);
+        let passed = match (&test.expected, &result) {
+            (Expected::Url(expected), Ok(Some(Resolution::Redirect { url, .. }))) => {
+                url == expected
+            }
+            (Expected::Text(expected), Ok(Some(Resolution::Text { text }))) => text == expected,
+            (Expected::NoMatch, Ok(None)) => true,
+            _ => false,
+        };
+        report.record(passed, || Failure {
+            path: format!("tests[{idx}]"),
+            input: test.input.clone(),
+            expected: match &test.expected {
+                Expected::Url(url) => url.clone(),
+                Expected::Text(text) => format!("text: {text}"),
+                Expected::NoMatch => "no match".to_string(),
+            },
+            actual: describe(&result),
+        });
+    }
+
+    for shortcut in shortcuts::collect(cfg) {
+        let single = shortcut
+            .example
+            .iter()
+            .map(|example| (format!("{}.example", shortcut.path), example));
+        let examples = shortcut
+            .examples
+            .iter()
+            .enumerate()
+            .map(|(idx, example)| (format!("{}.examples[{idx}]", shortcut.path), example));
+        for (path, example) in single.chain(examples) {
+            let (result, trace) = matching::explain(&cfg.matcher, Request::from_input(example));
+            let accepted = result.is_ok()
+                && trace
+                    .as_ref()
+                    .is_some_and(|trace| accepted_by(trace, &shortcut.path));
+            report.record(accepted, || Failure {
+                path,
+                input: example.clone(),
+                expected: format!("accepted by {}", shortcut.path),
+                actual: match (&result, trace.as_ref().and_then(resolved_by)) {
+                    (Ok(Some(_)), Some(step)) => format!("accepted by {}", step.path),
+                    _ => describe(&result),
+                },
+            });
+        }
+    }
+
+    report
+}
+
+/// A test result in the form the failure report shows it.
+fn describe(result: &Result<Option<Resolution>>) -> String {
+    match result {
+        Ok(Some(Resolution::Redirect { url, .. })) => url.clone(),
+        Ok(Some(Resolution::Text { text })) => format!("text: {text}"),
+        Ok(Some(Resolution::Accepted {}) | None) => "no match".to_string(),
+        Err(err) => format!("error: {err:#}"),
+    }
+}
+
+/// Whether the matcher at `path` (or a sub matcher of it) resolved the request.
+fn accepted_by(step: &Step, path: &str) -> bool {
+    let resolved = matches!(
+        step.outcome,
+        Outcome::Matched { .. } | Outcome::Delegated { .. }
+    );
+    (resolved && step.path == path) || step.steps.iter().any(|s| accepted_by(s, path))
+}
+
+/// The step of the matcher that resolved the request with its own `url` or
+/// `text`.
+fn resolved_by(step: &Step) -> Option<&Step> {
+    match step.outcome {
+        Outcome::Matched { .. } => Some(step),
+        Outcome::Delegated { .. } => step.steps.iter().find_map(resolved_by),
+        _ => None,
+    }
+}
+
+#[instrument(level = "debug", skip(args, config_path))]
+pub fn run(args: TestArgs, config_path: PathBuf) -> Result<()> {
+    let path = args.file.unwrap_or(config_path);
+    info!(path = %path.display(), "test start");
+
+    let cfg = crate::config::load(&path)?;
+    let report = run_tests(&cfg);
+    println!("{report}");
+
+    if !report.failures.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_failed_tests_and_examples() {
+        let input = "match:
+- prefix: animals/
+  match:
+  - exact: bear
+    example: animals/bear
+    url: https://bears.org
+  - exact: lion
+    examples: [animals/lion, animals/bear]
+    url: https://lions.org
+- exact: phone
+  text: '555'
+tests:
+- input: animals/bear
+  url: https://bears.org
+- input: phone
+  text: '555'
+- input: animals/lion
+  url: https://lion.org
+- input: cats
+  no-match: true
+";
+        let cfg = Config::parse(input).unwrap();
+        let report = run_tests(&cfg);
+        assert_eq!(report.passed, 5);
+        assert_eq!(
+            report.failures,
+            [
+                Failure {
+                    path: "tests[2]".into(),
+                    input: "animals/lion".into(),
+                    expected: "https://lion.org".into(),
+                    actual: "https://lions.org".into(),
+                },
+                Failure {
+                    path: "match[0].match[1].examples[1]".into(),
+                    input: "animals/bear".into(),
+                    expected: "accepted by match[0].match[1]".into(),
+                    actual: "accepted by match[0].match[0]".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_case_needs_one_expectation() {
+        let input =
+            "match:\n  exact: a\n  url: b\ntests:\n- input: a\n  url: b\n  no-match: true\n";
+        let err = Config::parse(input).unwrap_err().to_string();
+        assert!(
+            err.contains("test for `a` needs exactly one of `url`, `text` or `no-match: true`"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Report, Result};
+use tracing::{info, instrument};
+
+use crate::{
+    cli::ValidateArgs,
+    config::{InFile, ParseError},
+};
+
+#[instrument(level = "debug", skip(args, config_path))]
+pub fn run(args: ValidateArgs, config_path: PathBuf) -> Result<()> {
+    let path = args.file.unwrap_or(config_path);
+    info!(path = %path.display(), "validate start");
+
+    match crate::config::load(&path) {
+        Ok(_) => {
+            println!("{}: ok", path.display());
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("{}", diagnostic(&path, &err));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Formats a parse error as `file:line:column: message`, which editors and
+/// CI tools understand. The file is the included file the error is in, if
+/// it's not in the main one.
+fn diagnostic(path: &Path, err: &Report) -> String {
+    let Some(file) = err.downcast_ref::<InFile>() else {
+        return format!("{}: {err:#}", path.display());
+    };
+    let path = &file.0;
+    let Some(parse_err) = err.downcast_ref::<ParseError>() else {
+        // The path is already part of the diagnostic, so only show the errors
+        // below the one that names the file.
+        let marker = file.to_string();
+        let message: Vec<_> = err
+            .chain()
+            .skip_while(|cause| cause.to_string() != marker)
+            .skip(1)
+            .map(|cause| cause.to_string())
+            .collect();
+        return format!("{}: {}", path.display(), message.join(": "));
+    };
+    let message = &parse_err.message;
+    match parse_err.location {
+        Some((line, column)) => format!("{}:{line}:{column}: {message}", path.display()),
+        None => format!("{}: {message}", path.display()),
+    }
+}
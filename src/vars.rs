@@ -0,0 +1,244 @@
+//! Variables in configuration values: `${var.NAME}` refers to the `vars`
+//! section and `${env.NAME}` to the environment. They are replaced once, when
+//! the configuration is loaded.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use color_eyre::eyre::{Result, eyre};
+use serde::Deserialize;
+use serde::de::{self, Deserializer};
+
+use crate::config::Format;
+
+/// The variables of a configuration file, by name.
+pub type Vars = BTreeMap<String, String>;
+
+/// What kind of value a variable is inserted into, which decides how its
+/// value is escaped. `${var.NAME|raw}` inserts the value as it is anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// A `url`, `text` or `match-with` template. A `$` in a value is
+    /// inserted as `$$`, so it doesn't start a placeholder.
+    Template,
+    /// A `regex`, which matches the values literally.
+    Regex,
+    /// A `glob`, which matches the values literally.
+    Glob,
+    /// Any other value, e.g. an `exact` input. `$$` stands for a `$`.
+    Literal,
+}
+
+impl Field {
+    fn escape(self, value: &str) -> String {
+        match self {
+            Field::Template => value.replace('$', "$$"),
+            Field::Regex => regex::escape(value),
+            Field::Glob => value
+                .chars()
+                .flat_map(|c| match c {
+                    '*' | '?' | '{' | '}' | ',' | '\\' => vec!['\\', c],
+                    c => vec![c],
+                })
+                .collect(),
+            Field::Literal => value.to_string(),
+        }
+    }
+}
+
+/// The keys of a matcher whose values can contain variables, other than the
+/// `url` and `text`, which are expanded with [`deserialize_expanded_template`].
+pub(crate) const MATCHER_KEYS: [(&str, Field); 8] = [
+    ("prefix", Field::Literal),
+    ("suffix", Field::Literal),
+    ("contains", Field::Literal),
+    ("exact", Field::Literal),
+    ("glob", Field::Glob),
+    ("regex", Field::Regex),
+    ("match-with", Field::Template),
+    ("command", Field::Literal),
+];
+
+/// Replaces the variables in `value`, escaping their values for the `field`.
+/// Other placeholders, like `${1}`, are kept as they are, and so are escaped
+/// dollars (`$$`) in templates. Elsewhere, `$$` becomes a single `$`.
+pub fn expand(value: &str, vars: &Vars, field: Field) -> Result<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("$$") {
+            expanded.push_str(if field == Field::Template { "$$" } else { "$" });
+            rest = &rest[2..];
+            continue;
+        }
+        let variable = rest
+            .strip_prefix("${")
+            .and_then(|inner| Some(&inner[..inner.find('}')?]));
+        let replacement = match variable {
+            Some(inner) => lookup(inner, vars)?,
+            None => None,
+        };
+        match (variable, replacement) {
+            (Some(inner), Some((replacement, raw))) => {
+                if raw {
+                    expanded.push_str(&replacement);
+                } else {
+                    expanded.push_str(&field.escape(&replacement));
+                }
+                rest = &rest[inner.len() + 3..];
+            }
+            _ => {
+                expanded.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The value of the placeholder `${inner}` and whether it is to be inserted
+/// raw, or `None` if it isn't a variable.
+fn lookup(inner: &str, vars: &Vars) -> Result<Option<(String, bool)>> {
+    let (inner, raw) = match inner.strip_suffix("|raw") {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+    if let Some(name) = inner.strip_prefix("var.") {
+        let value = vars
+            .get(name)
+            .ok_or_else(|| eyre!("undefined variable `{name}`"))?;
+        return Ok(Some((value.clone(), raw)));
+    }
+    if let Some(name) = inner.strip_prefix("env.") {
+        let value =
+            std::env::var(name).map_err(|_| eyre!("environment variable `{name}` is not set"))?;
+        return Ok(Some((value, raw)));
+    }
+    Ok(None)
+}
+
+/// The `vars` section of a configuration file, with environment variables
+/// in its values expanded.
+pub(crate) fn declared(cfg: &str, format: Format) -> Result<Vars> {
+    #[derive(Deserialize)]
+    struct Header {
+        #[serde(default)]
+        vars: Vars,
+    }
+
+    let header: Header = format.parse(cfg)?;
+    header
+        .vars
+        .into_iter()
+        .map(|(name, value)| {
+            let value = expand(&value, &Vars::new(), Field::Literal)
+                .map_err(|err| eyre!("invalid variable `{name}`: {err}"))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Vars> = const { RefCell::new(Vars::new()) };
+}
+
+/// Runs `f` with `vars` as the variables that are expanded while matchers are
+/// deserialized.
+pub(crate) fn scope<T>(vars: Vars, f: impl FnOnce() -> T) -> T {
+    let previous = ACTIVE.replace(vars);
+    let result = f();
+    ACTIVE.set(previous);
+    result
+}
+
+/// Like [`expand`], with the variables of the current [`scope`].
+pub(crate) fn expand_active(value: &str, field: Field) -> Result<String> {
+    ACTIVE.with_borrow(|vars| expand(value, vars, field))
+}
+
+/// Deserializes an optional string with the variables of the current
+/// [`scope`] expanded, for values nested deeper than the [`MATCHER_KEYS`].
+pub(crate) fn deserialize_expanded<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    deserialize_field(deserializer, Field::Literal)
+}
+
+/// Like [`deserialize_expanded`], for a template, e.g. the `url` of a keyword.
+pub(crate) fn deserialize_expanded_template<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    deserialize_field(deserializer, Field::Template)
+}
+
+fn deserialize_field<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    field: Field,
+) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| expand_active(&value, field).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Like [`deserialize_expanded`], for a list of strings.
+pub(crate) fn deserialize_expanded_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| expand_active(value, Field::Literal).map_err(de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_variables_only() {
+        let vars = Vars::from([("host".to_string(), "jira.example".to_string())]);
+        let expanded = expand(
+            "https://${var.host}/$1/${1|upper}/$${var.host}",
+            &vars,
+            Field::Template,
+        )
+        .unwrap();
+        assert_eq!(expanded, "https://jira.example/$1/${1|upper}/$${var.host}");
+
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(
+            expand("${env.HOME}/x", &vars, Field::Template).unwrap(),
+            format!("{home}/x")
+        );
+    }
+
+    #[test]
+    fn values_are_escaped_for_the_field() {
+        let vars = Vars::from([("v".to_string(), "a.$1*{b}".to_string())]);
+        let expand = |value, field| expand(value, &vars, field).unwrap();
+        assert_eq!(expand("${var.v}/$$", Field::Template), "a.$$1*{b}/$$");
+        assert_eq!(expand("${var.v}$", Field::Regex), r"a\.\$1\*\{b\}$");
+        assert_eq!(expand("${var.v}/**", Field::Glob), r"a.$1\*\{b\}/**");
+        assert_eq!(expand("${var.v}/$$", Field::Literal), "a.$1*{b}/$");
+        assert_eq!(expand("${var.v|raw}.+", Field::Regex), "a.$1*{b}.+");
+    }
+
+    #[test]
+    fn undefined_variables_are_errors() {
+        let err = expand("https://${var.hots}", &Vars::new(), Field::Template).unwrap_err();
+        assert_eq!(err.to_string(), "undefined variable `hots`");
+        let err = expand(
+            "${env.SHORTCUT_CATAPULT_UNSET}",
+            &Vars::new(),
+            Field::Literal,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "environment variable `SHORTCUT_CATAPULT_UNSET` is not set"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak, mpsc};
+
+use arc_swap::ArcSwapOption;
+use color_eyre::eyre::{Result, eyre};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::OnceCell;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::config::{self, Config, Format};
+use crate::{include, test};
+
+/// A configuration that follows the file it was loaded from.
+///
+/// The compiled matcher tree is swapped atomically whenever the file changes.
+/// If the new contents fail to load, the last good configuration stays active.
+pub struct LiveConfig {
+    path: PathBuf,
+    current: ArcSwapOption<Config>,
+    last_error: Mutex<Option<String>>,
+    watcher: OnceCell<Mutex<Watching>>,
+}
+
+impl LiveConfig {
+    /// Loads the configuration at `path`.
+    ///
+    /// A configuration that fails to load is not an error here: the failure is
+    /// reported by [`LiveConfig::current`] until a reload succeeds.
+    pub fn load(path: PathBuf) -> Arc<Self> {
+        let live = Arc::new(LiveConfig {
+            path,
+            current: ArcSwapOption::empty(),
+            last_error: Mutex::new(None),
+            watcher: OnceCell::new(),
+        });
+        live.reload().ok();
+        live
+    }
+
+    /// The active configuration or, if none could be loaded yet, the most
+    /// recent load error.
+    pub fn current(&self) -> Result<Arc<Config>, String> {
+        match self.current.load_full() {
+            Some(cfg) => Ok(cfg),
+            None => Err(self
+                .last_error
+                .lock()
+                .expect("last_error lock poisoned")
+                .clone()
+                .unwrap_or_else(|| "configuration not loaded".to_string())),
+        }
+    }
+
+    /// Re-reads the configuration file, keeping the active configuration if
+    /// the file cannot be loaded.
+    #[instrument(level = "info", skip(self), fields(path = %self.path.display()))]
+    pub fn reload(&self) -> Result<()> {
+        let mut last_error = self.last_error.lock().expect("last_error lock poisoned");
+        match config::load(&self.path).and_then(|cfg| self.check_tests(cfg)) {
+            Ok(cfg) => {
+                self.current.store(Some(Arc::new(cfg)));
+                *last_error = None;
+                info!("configuration loaded");
+                Ok(())
+            }
+            Err(err) => {
+                error!("failed to load configuration, keeping previous one: {err:?}");
+                *last_error = Some(err.to_string());
+                Err(err)
+            }
+        }
+    }
+
+    /// Refuses a new configuration whose embedded tests fail, unless there is
+    /// no configuration to keep.
+    fn check_tests(&self, cfg: Config) -> Result<Config> {
+        let report = test::run_tests(&cfg);
+        if report.failures.is_empty() {
+            return Ok(cfg);
+        }
+        if self.current.load().is_none() {
+            warn!("embedded tests of the configuration fail:\n{report}");
+            return Ok(cfg);
+        }
+        Err(eyre!("embedded tests of the configuration fail:\n{report}"))
+    }
+
+    /// Starts watching the configuration files for changes.
+    ///
+    /// The directories of the files are watched rather than the files
+    /// themselves so that editors that save by replacing the file are picked
+    /// up, too. The `conf.d` directory is watched for new fragments.
+    pub fn watch(self: &Arc<Self>) -> Result<()> {
+        let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let watcher = notify::recommended_watcher(tx)?;
+        self.watcher
+            .set(Mutex::new(Watching {
+                watcher,
+                dirs: HashSet::new(),
+            }))
+            .map_err(|_| eyre!("config is already being watched"))?;
+        self.update_watches()?;
+
+        // Events are handled on a thread of their own because the watcher
+        // can't be changed from within its event handler.
+        let live: Weak<LiveConfig> = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            for event in rx {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        error!("config watcher error: {err}");
+                        continue;
+                    }
+                };
+                let Some(live) = live.upgrade() else {
+                    return;
+                };
+                let relevant = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) && event.paths.iter().any(|p| live.affects(p));
+                if !relevant {
+                    continue;
+                }
+                debug!(?event, "config file changed");
+                // A `conf.d` that was just created is watched before it's
+                // read, so that no fragment added to it in between is missed.
+                live.update_watches().ok();
+                if live.reload().is_ok() {
+                    live.update_watches().ok();
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// The files the active configuration was loaded from, and the main file.
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.path.clone()];
+        if let Some(cfg) = self.current.load_full() {
+            files.extend(cfg.sources.iter().cloned());
+        }
+        files
+    }
+
+    /// Whether a change to `path` can change the configuration. That includes
+    /// creating or removing the `conf.d` directory.
+    fn affects(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        let conf_d = include::conf_d_dir(&self.path);
+        let fragment = path.parent() == Some(normalize_dir(&conf_d).as_path())
+            && Format::from_extension(&path).is_some();
+        path == normalize(&conf_d)
+            || fragment
+            || self.files().iter().any(|file| normalize(file) == path)
+    }
+
+    /// Makes sure the directories of all configuration files are watched.
+    fn update_watches(&self) -> Result<()> {
+        let Some(watching) = self.watcher.get() else {
+            return Ok(());
+        };
+        let mut watching = watching.lock().expect("watcher lock poisoned");
+        // A removed directory isn't watched anymore, even if it comes back.
+        watching.dirs.retain(|dir| dir.is_dir());
+        let mut dirs: Vec<_> = self.files().iter().map(|file| parent(file)).collect();
+        let conf_d = include::conf_d_dir(&self.path);
+        if conf_d.is_dir() {
+            dirs.push(conf_d);
+        }
+        for dir in dirs {
+            let key = normalize_dir(&dir);
+            if watching.dirs.contains(&key) {
+                continue;
+            }
+            watching.watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            info!(dir = %dir.display(), "watching configuration for changes");
+            watching.dirs.insert(key);
+        }
+        Ok(())
+    }
+}
+
+/// The file system watcher and the directories it watches.
+struct Watching {
+    watcher: RecommendedWatcher,
+    dirs: HashSet<PathBuf>,
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+fn normalize_dir(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// A path that compares equal to other paths to the same file, even if the
+/// file itself doesn't exist (anymore).
+fn normalize(path: &Path) -> PathBuf {
+    let dir = normalize_dir(&parent(path));
+    match path.file_name() {
+        Some(name) => dir.join(name),
+        None => dir,
+    }
+}
@@ -68,3 +68,65 @@ fn missing_config_reports_path() {
         .code(3)
         .stderr(predicate::str::contains(path.to_string_lossy()));
 }
+
+#[test]
+fn placeholders_are_encoded_for_the_query() {
+    let cfg = "match:\n  prefix: s/\n  url: https://search.example/find?q=$2\n";
+    run_apply(cfg, Some("s/c++ & rust"), None)
+        .success()
+        .stdout(predicate::eq(
+            "https://search.example/find?q=c%2B%2B%20%26%20rust",
+        ));
+}
+
+#[test]
+fn raw_placeholders_are_inserted_verbatim() {
+    let cfg = "match:\n  prefix: s/\n  url: https://search.example/find?$raw2\n";
+    run_apply(cfg, Some("s/q=a&lang=en"), None)
+        .success()
+        .stdout(predicate::eq("https://search.example/find?q=a&lang=en"));
+}
+
+#[test]
+fn typed_question_marks_and_hashes_are_kept() {
+    let cfg = "match:\n  prefix: s/\n  url: https://search.example/?q=$2\n";
+    run_apply(cfg, Some("s/c# tutorial"), None)
+        .success()
+        .stdout(predicate::eq("https://search.example/?q=c%23%20tutorial"));
+    run_apply(cfg, Some("s/what is rust?"), None)
+        .success()
+        .stdout(predicate::eq(
+            "https://search.example/?q=what%20is%20rust%3F",
+        ));
+}
+
+#[test]
+fn url_is_split_into_path_and_query() {
+    let cfg = "match:\n  input: path\n  exact: Hello\n  url: https://example.com?q=$1\n";
+    run_apply(cfg, Some("/Hello?utm=1"), None)
+        .success()
+        .stdout(predicate::eq("https://example.com?q=Hello"));
+    run_apply(cfg, Some("http://localhost:8081/Hello?utm=1#top"), None)
+        .success()
+        .stdout(predicate::eq("https://example.com?q=Hello"));
+    run_apply(EXACT_CONFIG, Some("/Hello?utm=1"), None)
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn matcher_can_match_on_the_target() {
+    let cfg =
+        "match:\n  input: target\n  regex: ^docs\\?page=(\\d+)$\n  url: https://docs.example/$1\n";
+    run_apply(cfg, Some("docs?page=42"), None)
+        .success()
+        .stdout(predicate::eq("https://docs.example/42"));
+}
+
+#[test]
+fn missing_query_parameter_does_not_match() {
+    let cfg = "match:\n  input: query.q\n  prefix: ''\n  url: https://search.example?q=$2\n";
+    run_apply(cfg, Some("/search?lang=en"), None)
+        .failure()
+        .code(2);
+}
@@ -63,7 +63,12 @@ async fn not_found_returns_404() {
     let resp = client.get(&url).send().await.expect("request");
     eprintln!("status2: {}", resp.status());
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
-    assert!(resp.text().await.unwrap().is_empty());
+    let body = resp.text().await.unwrap();
+    assert!(
+        body.contains("No shortcut for <code>World</code>"),
+        "{body}"
+    );
+    assert!(body.contains(r#"<a href="/Hello">Hello</a>"#), "{body}");
 
     handle.abort();
 }
@@ -86,3 +91,375 @@ async fn prefix_path_redirects() {
 
     handle.abort();
 }
+
+/// Polls `url` until its `Location` header equals `expected` or a timeout
+/// expires, since the config watcher reloads asynchronously.
+async fn wait_for_location(client: &reqwest::Client, url: &str, expected: &str) -> bool {
+    for _ in 0..50 {
+        let resp = client.get(url).send().await.expect("request");
+        if resp
+            .headers()
+            .get("location")
+            .is_some_and(|location| location == expected)
+        {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn config_change_is_reloaded() {
+    let (handle, addr, file) = spawn_server(EXACT_CONFIG).await;
+
+    let url = format!("http://{}:{}/Hello", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    assert!(wait_for_location(&client, &url, "https://example.com?q=Hello").await);
+
+    file.write_str("match:\n  exact: Hello\n  url: https://changed.example\n")
+        .expect("write config");
+    assert!(wait_for_location(&client, &url, "https://changed.example").await);
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn conf_d_created_later_is_watched() {
+    let (handle, addr, file) = spawn_server(EXACT_CONFIG).await;
+
+    let url = format!("http://{}:{}/extra", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let conf_d = file.path().parent().unwrap().join("conf.d");
+    std::fs::create_dir(&conf_d).expect("create conf.d");
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    std::fs::write(
+        conf_d.join("extra.yml"),
+        "match:\n  exact: extra\n  url: https://extra.example\n",
+    )
+    .expect("write fragment");
+    assert!(wait_for_location(&client, &url, "https://extra.example").await);
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn broken_config_keeps_previous() {
+    let (handle, addr, file) = spawn_server(EXACT_CONFIG).await;
+
+    let url = format!("http://{}:{}/Hello", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    file.write_str("match:\n  regex: (unclosed\n  url: https://broken.example\n")
+        .expect("write config");
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let resp = client.get(&url).send().await.expect("request");
+    assert_eq!(resp.status(), StatusCode::FOUND);
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "https://example.com?q=Hello"
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn explain_returns_trace_as_json() {
+    let (handle, addr, _file) = spawn_server(PREFIX_CONFIG).await;
+
+    let url = format!("http://{}:{}/_explain/animals/bear", addr.ip(), addr.port());
+    let resp = reqwest::get(&url).await.expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(body["input"], "animals/bear");
+    assert_eq!(body["url"], "https://example.com/bear");
+    assert_eq!(body["trace"]["path"], "match");
+    assert_eq!(body["trace"]["matcher"], "prefix `animals/`");
+    assert_eq!(body["trace"]["outcome"], "matched");
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn query_parameters_are_forwarded() {
+    let config = "match:\n- input: path\n  exact: search\n  match:\n    input: query.q\n    prefix: 'gh '\n    url: https://github.com/search?q=$2&lang=${query.lang}\n";
+    let (handle, addr, _file) = spawn_server(config).await;
+
+    let url = format!(
+        "http://{}:{}/search?q=gh+c%2B%2B+%26+rust&lang=en",
+        addr.ip(),
+        addr.port()
+    );
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let resp = client.get(&url).send().await.expect("request");
+    assert_eq!(resp.status(), StatusCode::FOUND);
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "https://github.com/search?q=c%2B%2B%20%26%20rust&lang=en"
+    );
+
+    handle.abort();
+}
+
+const RESPONSE_CONFIG: &str = r#"status: 308
+match:
+- exact: moved
+  url: https://example.com/moved
+  status: 301
+- exact: docs
+  url: https://example.com/docs
+- exact: external
+  url: https://example.com/?a=1&b=2
+  response: interstitial
+- exact: phone
+  text: "+1 555 0100"
+"#;
+
+#[tokio::test]
+async fn response_modes() {
+    let (handle, addr, _file) = spawn_server(RESPONSE_CONFIG).await;
+
+    let base = format!("http://{}:{}", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let resp = client.get(format!("{base}/moved")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "https://example.com/moved"
+    );
+
+    let resp = client.get(format!("{base}/docs")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let resp = client.get(format!("{base}/external")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("location").is_none());
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let body = resp.text().await.unwrap();
+    assert!(body.contains(r#"href="https://example.com/?a=1&amp;b=2""#));
+
+    let resp = client.get(format!("{base}/phone")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(resp.text().await.unwrap(), "+1 555 0100");
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn landing_page_lists_shortcuts() {
+    let config = "match:\n- prefix: animals/\n  name: Animals\n  description: Pages about <animals>\n  example: animals/bear\n  url: https://example.com/$2\n- exact: phone\n  text: '555'\n";
+    let (handle, addr, _file) = spawn_server(config).await;
+
+    let base = format!("http://{}:{}", addr.ip(), addr.port());
+    let resp = reqwest::get(format!("{base}/")).await.expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<td>Animals</td>"), "{body}");
+    assert!(body.contains("Pages about &lt;animals&gt;"), "{body}");
+    assert!(body.contains(r#"<a href="/animals/bear">"#), "{body}");
+    assert!(
+        body.contains("<code>https://example.com/$2</code>"),
+        "{body}"
+    );
+    assert!(body.contains("exact `phone`"), "{body}");
+
+    let resp = reqwest::get(format!("{base}/_shortcuts.json"))
+        .await
+        .expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(body[0]["path"], "match[0]");
+    assert_eq!(body[0]["name"], "Animals");
+    assert_eq!(body[0]["example"], "animals/bear");
+    assert_eq!(body[0]["url"], "https://example.com/$2");
+    assert_eq!(body[1]["matchers"][0], "exact `phone`");
+    assert_eq!(body[1]["text"], "555");
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn fallback_replaces_404() {
+    let config = "fallback: https://duckduckgo.com/?q=$1\nstatus: 307\nmatch:\n  exact: Hello\n  url: https://example.com\n";
+    let (handle, addr, _file) = spawn_server(config).await;
+
+    let url = format!("http://{}:{}/rust%20lang", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let resp = client.get(&url).send().await.expect("request");
+    assert_eq!(resp.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "https://duckduckgo.com/?q=rust%20lang"
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn opensearch_descriptions() {
+    let config = "match:\n- prefix: 'gh '\n  name: GitHub\n  url: https://github.com/$2\n- exact: Hello\n  url: https://example.com\n";
+    let (handle, addr, _file) = spawn_server(config).await;
+
+    let base = format!("http://{}:{}", addr.ip(), addr.port());
+    let resp = reqwest::get(format!("{base}/_opensearch.xml"))
+        .await
+        .expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()["content-type"],
+        "application/opensearchdescription+xml"
+    );
+    let body = resp.text().await.unwrap();
+    assert!(
+        body.contains(&format!(r#"template="{base}/{{searchTerms}}""#)),
+        "{body}"
+    );
+
+    let resp = reqwest::get(format!("{base}/_opensearch/gh%20.xml"))
+        .await
+        .expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<ShortName>GitHub</ShortName>"), "{body}");
+    assert!(
+        body.contains(&format!(r#"template="{base}/gh%20{{searchTerms}}""#)),
+        "{body}"
+    );
+
+    let resp = reqwest::get(format!("{base}/_opensearch/Hello.xml"))
+        .await
+        .expect("request");
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let body = reqwest::get(format!("{base}/"))
+        .await
+        .expect("request")
+        .text()
+        .await
+        .unwrap();
+    assert!(
+        body.contains(r#"<link rel="search" type="application/opensearchdescription+xml" title="shortcut-catapult" href="/_opensearch.xml">"#),
+        "{body}"
+    );
+    assert!(
+        body.contains(r#"<link rel="search" type="application/opensearchdescription+xml" title="GitHub" href="/_opensearch/gh%20.xml">"#),
+        "{body}"
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn suggestions_complete_shortcuts() {
+    let config = "match:\n- prefix: 'gh '\n  description: GitHub search\n  url: https://github.com/search?q=$2\n- prefix: animals/\n  match:\n  - exact: bear\n    name: Bears\n    url: https://bears.org\n";
+    let (handle, addr, _file) = spawn_server(config).await;
+
+    let base = format!("http://{}:{}", addr.ip(), addr.port());
+    let resp = reqwest::get(format!("{base}/_suggest?q=an"))
+        .await
+        .expect("request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()["content-type"],
+        "application/x-suggestions+json"
+    );
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(
+        body,
+        serde_json::json!([
+            "an",
+            ["animals/", "animals/bear"],
+            ["", "Bears"],
+            [format!("{base}/animals/"), format!("{base}/animals/bear")]
+        ])
+    );
+
+    let body: serde_json::Value = reqwest::get(format!("{base}/_suggest?prefix=animals%2F&q=b"))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json body");
+    assert_eq!(body[1], serde_json::json!(["bear"]));
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn config_with_failing_tests_keeps_previous() {
+    let (handle, addr, file) = spawn_server(EXACT_CONFIG).await;
+
+    let url = format!("http://{}:{}/Hello", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    assert!(wait_for_location(&client, &url, "https://example.com?q=Hello").await);
+
+    file.write_str("match:\n  exact: Hello\n  url: https://broken.example\ntests:\n- input: Hello\n  url: https://example.com?q=Hello\n")
+        .expect("write config");
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let resp = client.get(&url).send().await.expect("request");
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "https://example.com?q=Hello"
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn conf_d_fragment_changes_are_reloaded() {
+    let (handle, addr, file) = spawn_server(EXACT_CONFIG).await;
+    let conf_d = file.path().parent().unwrap().join("conf.d");
+    std::fs::create_dir(&conf_d).expect("create conf.d");
+    // Touch the main file so the daemon notices the new directory.
+    file.write_str(EXACT_CONFIG).expect("write config");
+
+    let url = format!("http://{}:{}/bear", addr.ip(), addr.port());
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    std::fs::write(
+        conf_d.join("animals.yml"),
+        "match:\n  exact: bear\n  url: https://bears.org\n",
+    )
+    .expect("write fragment");
+    assert!(wait_for_location(&client, &url, "https://bears.org").await);
+
+    std::fs::write(
+        conf_d.join("animals.yml"),
+        "match:\n  exact: bear\n  url: https://bears.example\n",
+    )
+    .expect("write fragment");
+    assert!(wait_for_location(&client, &url, "https://bears.example").await);
+
+    handle.abort();
+}
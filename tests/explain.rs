@@ -0,0 +1,54 @@
+use assert_cmd::Command;
+use assert_fs::fixture::NamedTempFile;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+const CONFIG: &str = "match:\n- exact: Elephant\n  url: https://kagi.com?q=Elephant\n- prefix: animals/\n  match:\n    exact: bear\n    url: https://bears.org\n";
+
+fn run_explain(config: &str, args: &[&str]) -> assert_cmd::assert::Assert {
+    let file = NamedTempFile::new("config.yml").expect("temp file");
+    file.write_str(config).expect("write config");
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("--config")
+        .arg(file.path())
+        .arg("explain")
+        .args(args);
+    cmd.assert()
+}
+
+#[test]
+fn prints_trace_tree() {
+    run_explain(CONFIG, &["animals/bear"])
+        .success()
+        .stdout(predicate::eq(
+            "\
+match: list of 2 matchers on `animals/bear` => sub matcher redirects to https://bears.org
+  match[0]: exact `Elephant` on `animals/bear` => no match: `animals/bear` is not `Elephant`
+  match[1]: prefix `animals/` on `animals/bear` => sub matcher redirects to https://bears.org
+    match[1].match: exact `bear` on `bear` => redirect to https://bears.org
+result: https://bears.org
+",
+        ));
+}
+
+#[test]
+fn no_match_exit_code_two() {
+    run_explain(CONFIG, &["animals/lion"])
+        .failure()
+        .code(2)
+        .stdout(predicate::str::contains(
+            "match[1].match: exact `bear` on `lion` => no match: `lion` is not `bear`",
+        ))
+        .stdout(predicate::str::ends_with("result: no match\n"));
+}
+
+#[test]
+fn json_output() {
+    run_explain(CONFIG, &["--json", "Elephant"])
+        .success()
+        .stdout(predicate::str::contains(
+            r#""url": "https://kagi.com?q=Elephant""#,
+        ))
+        .stdout(predicate::str::contains(r#""path": "match[0]""#))
+        .stdout(predicate::str::contains(r#""outcome": "matched""#));
+}
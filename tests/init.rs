@@ -0,0 +1,38 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+fn init(args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("init").args(args).assert()
+}
+
+#[test]
+fn creates_config_and_directory() {
+    let dir = assert_fs::TempDir::new().expect("temp dir");
+    let config = dir.child("shortcut-catapult/config.yml");
+    let path = config.path().to_str().expect("utf-8 path");
+
+    init(&["--template", "search-engines", path])
+        .success()
+        .stdout(predicate::str::starts_with("created "));
+    config.assert(predicate::str::contains("fallback: https://duckduckgo.com"));
+
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("test").arg(path).assert().success();
+}
+
+#[test]
+fn keeps_existing_config_unless_forced() {
+    let config = assert_fs::NamedTempFile::new("config.yml").expect("temp file");
+    config.write_str("match: []\n").expect("write config");
+    let path = config.path().to_str().expect("utf-8 path");
+
+    init(&[path])
+        .failure()
+        .stderr(predicate::str::contains("already exists, use --force"));
+    config.assert("match: []\n");
+
+    init(&["--force", "--template", "developer", path]).success();
+    config.assert(predicate::str::contains("name: docs.rs"));
+}
@@ -0,0 +1,73 @@
+use assert_cmd::Command;
+use assert_fs::fixture::NamedTempFile;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+const ENGINE_CONFIG: &str = "\
+match:
+- query: engine
+  value: work
+  match:
+    query: s
+    url: https://intranet.example/search?q=$1
+- query: s
+  url: https://duckduckgo.com/?q=$1
+";
+
+fn run_apply(config: &str, arg: Option<&str>, stdin: Option<&str>) -> assert_cmd::assert::Assert {
+    let file = NamedTempFile::new("config.yml").expect("temp file");
+    file.write_str(config).expect("write config");
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("--config").arg(file.path()).arg("apply");
+    if let Some(a) = arg {
+        cmd.arg(a);
+    }
+    if let Some(input) = stdin {
+        cmd.write_stdin(input);
+    }
+    cmd.assert()
+}
+
+#[test]
+fn dispatches_on_parameter_value() {
+    run_apply(ENGINE_CONFIG, Some("/?s=rust+traits&engine=work"), None)
+        .success()
+        .stdout(predicate::eq(
+            "https://intranet.example/search?q=rust%20traits",
+        ));
+}
+
+#[test]
+fn falls_through_on_other_value() {
+    run_apply(ENGINE_CONFIG, Some("/?s=rust&engine=web"), None)
+        .success()
+        .stdout(predicate::eq("https://duckduckgo.com/?q=rust"));
+}
+
+#[test]
+fn missing_parameter_exit_code_two() {
+    run_apply(ENGINE_CONFIG, Some("/?engine=work"), None)
+        .failure()
+        .code(2)
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn value_regex_constraint() {
+    let cfg = "match:\n  query: id\n  value-regex: ^\\d+$\n  match:\n    prefix: ''\n    url: https://tickets.example/$2\n";
+    run_apply(cfg, Some("/?id=1234"), None)
+        .success()
+        .stdout(predicate::eq("https://tickets.example/1234"));
+    run_apply(cfg, Some("/?id=abc"), None).failure().code(2);
+}
+
+#[test]
+fn value_and_value_regex_are_exclusive() {
+    let cfg = "match:\n  query: id\n  value: a\n  value-regex: b\n  url: https://x.example\n";
+    run_apply(cfg, Some("/?id=a"), None)
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "invalid `query` matcher: use either `value` or `value-regex`, not both",
+        ));
+}
@@ -55,3 +55,36 @@ fn list_picks_first_match() {
         .success()
         .stdout(predicate::eq("https://animals.example/Bea.pdf"));
 }
+
+#[test]
+fn invalid_regex_is_reported_at_load() {
+    let cfg = "match:\n  regex: (unclosed\n  url: https://example.com\n";
+    run_apply(cfg, Some("anything"), None)
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains("invalid regex `(unclosed`"));
+}
+
+#[test]
+fn filters_apply_to_match_with_and_url() {
+    let cfg = "match:\n  regex: ^(\\w+)/(.*)$\n  match-with: ${1|lower}\n  match:\n    exact: docs\n    url: https://docs.example/${1|slug}\n";
+    run_apply(cfg, Some("DOCS/Getting Started"), None)
+        .success()
+        .stdout(predicate::eq("https://docs.example/docs"));
+}
+
+#[test]
+fn named_groups_are_placeholders() {
+    let cfg = "match:\n  regex: ^(?P<project>[a-z]+)-(?P<ticket>\\d+)$\n  url: https://jira.example/browse/${project|upper}-${ticket}\n";
+    run_apply(cfg, Some("ab-123"), None)
+        .success()
+        .stdout(predicate::eq("https://jira.example/browse/AB-123"));
+}
+
+#[test]
+fn two_digit_groups_do_not_collide() {
+    let cfg = "match:\n  regex: (a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\n  url: https://x.example/$10$1\n";
+    run_apply(cfg, Some("abcdefghij"), None)
+        .success()
+        .stdout(predicate::eq("https://x.example/ja"));
+}
@@ -0,0 +1,42 @@
+use assert_cmd::Command;
+use serde_json::Value;
+
+fn schema() -> Value {
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    let output = cmd.arg("schema").assert().success().get_output().clone();
+    serde_json::from_slice(&output.stdout).expect("schema is JSON")
+}
+
+#[test]
+fn schema_covers_every_matcher() {
+    let schema = schema();
+    assert_eq!(schema["required"], serde_json::json!(["match"]));
+    let defs = &schema["$defs"];
+    let variants = defs["MatcherConfig"]["anyOf"].as_array().expect("anyOf");
+    assert_eq!(variants.len(), 15);
+    for kind in [
+        "exact", "prefix", "suffix", "contains", "fuzzy", "glob", "regex", "query", "ref",
+        "keywords", "command", "all", "any", "not",
+    ] {
+        let name = format!("{}{}MatcherConfig", kind[..1].to_uppercase(), &kind[1..]);
+        assert_eq!(defs[&name]["required"][0], kind, "{name}");
+        assert_eq!(defs[&name]["additionalProperties"], false, "{name}");
+    }
+}
+
+#[test]
+fn schema_has_defaults() {
+    let schema = schema();
+    let defs = &schema["$defs"];
+    let exact = &defs["ExactMatcherConfig"]["properties"];
+    assert_eq!(exact["case-sensitive"]["default"], false);
+    assert_eq!(exact["trim"]["default"], true);
+    assert_eq!(
+        defs["FuzzyMatcherConfig"]["properties"]["tolerance"]["default"],
+        3
+    );
+    assert_eq!(
+        defs["RedirectStatus"]["enum"],
+        serde_json::json!([301, 302, 303, 307, 308])
+    );
+}
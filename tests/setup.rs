@@ -0,0 +1,7 @@
+// `init` fails if an error report hook is already installed, which creating
+// any report does. The unit tests create plenty, so this runs on its own.
+
+#[test]
+fn init_ok() {
+    shortcut_catapult::init(None).expect("init should not error");
+}
@@ -0,0 +1,40 @@
+use assert_cmd::Command;
+use assert_fs::fixture::NamedTempFile;
+use assert_fs::prelude::*;
+
+fn run_test(config: &str) -> assert_cmd::assert::Assert {
+    let file = NamedTempFile::new("config.yml").expect("temp file");
+    file.write_str(config).expect("write config");
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("test").arg(file.path());
+    cmd.assert()
+}
+
+const CONFIG: &str = "match:
+- prefix: 'gh '
+  examples: [gh rust]
+  url: https://github.com/search?q=$2
+- exact: Hello
+  url: https://example.com
+tests:
+- input: Hello
+  url: https://example.com
+- input: World
+  no-match: true
+";
+
+#[test]
+fn passing_tests_succeed() {
+    run_test(CONFIG).success().stdout("3 passed, 0 failed\n");
+}
+
+#[test]
+fn failing_tests_show_a_diff() {
+    let config = format!("{CONFIG}- input: gh c++\n  url: https://github.com/search?q=c++\n");
+    run_test(&config).failure().code(1).stdout(
+        "FAIL tests[2]: gh c++\n  \
+         - https://github.com/search?q=c++\n  \
+         + https://github.com/search?q=c%2B%2B\n\
+         3 passed, 1 failed\n",
+    );
+}
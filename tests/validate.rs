@@ -0,0 +1,112 @@
+use assert_cmd::Command;
+use assert_fs::fixture::NamedTempFile;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+fn run_validate(config: &str) -> (assert_cmd::assert::Assert, NamedTempFile) {
+    run_validate_file("config.yml", config)
+}
+
+fn run_validate_file(name: &str, config: &str) -> (assert_cmd::assert::Assert, NamedTempFile) {
+    let file = NamedTempFile::new(name).expect("temp file");
+    file.write_str(config).expect("write config");
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("validate").arg(file.path());
+    (cmd.assert(), file)
+}
+
+#[test]
+fn valid_config_succeeds() {
+    let (assert, _file) = run_validate("match:\n  exact: Hello\n  url: https://example.com\n");
+    assert.success().stdout(predicate::str::ends_with(": ok\n"));
+}
+
+#[test]
+fn invalid_config_reports_location() {
+    let config = "match:\n- exact: Hello\n  url: https://example.com\n- regex: (open\n  url: https://example.com\n";
+    let (assert, file) = run_validate(config);
+    let expected = format!(
+        "{}:4:3: match[1]: invalid `regex` matcher: invalid regex `(open`",
+        file.path().display()
+    );
+    assert
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with(expected));
+}
+
+#[test]
+fn uses_config_option_without_file_argument() {
+    let file = NamedTempFile::new("config.yml").expect("temp file");
+    file.write_str("match:\n  fuzzy: Hello\n  tolerance: many\n  url: https://example.com\n")
+        .expect("write config");
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("--config").arg(file.path()).arg("validate");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "invalid `fuzzy` matcher: invalid type: string \"many\", expected u32",
+        ));
+}
+
+#[test]
+fn error_in_included_file_names_that_file() {
+    let dir = assert_fs::TempDir::new().expect("temp dir");
+    let config = dir.child("config.yml");
+    config
+        .write_str("include: [team/*.yml]\nmatch:\n  exact: Hello\n  url: https://example.com\n")
+        .expect("write config");
+    let team = dir.child("team/shared.yml");
+    team.write_str("match:\n- exact: bear\n  ur: https://bears.org\n")
+        .expect("write team config");
+
+    let mut cmd = Command::cargo_bin("shortcut-catapult").expect("binary exists");
+    cmd.arg("validate").arg(config.path());
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with(format!(
+            "{}:2:3: match[0]: invalid `exact` matcher: unknown field `ur`",
+            team.path().display()
+        )));
+}
+
+#[test]
+fn invalid_fallback_is_reported_once() {
+    let (assert, file) = run_validate(
+        "fallback: https://example.com/${1|nope}\nmatch:\n  exact: Hello\n  url: https://example.com\n",
+    );
+    assert
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with(format!(
+            "{}: invalid `fallback`: ",
+            file.path().display()
+        )));
+}
+
+#[test]
+fn toml_and_json_errors_report_location() {
+    let config = "[[match]]\nexact = \"Hello\"\nurl = \"https://example.com\"\n\n[[match]]\nregex = \"(open\"\nurl = \"https://example.com\"\n";
+    let (assert, file) = run_validate_file("config.toml", config);
+    let expected = format!(
+        "{}:5:1: invalid `regex` matcher: invalid regex `(open`",
+        file.path().display()
+    );
+    assert
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with(expected));
+
+    let config = "{\n  \"match\": [\n    {\"exact\": \"Hello\", \"url\": \"https://example.com\"},\n    {\"regex\": \"(open\", \"url\": \"https://example.com\"}\n  ]\n}\n";
+    let (assert, file) = run_validate_file("config.json", config);
+    let expected = format!(
+        "{}:4:52: invalid `regex` matcher: invalid regex `(open`",
+        file.path().display()
+    );
+    assert
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with(expected));
+}